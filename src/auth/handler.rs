@@ -1,23 +1,76 @@
-
 use argon2::password_hash::SaltString;
-use argon2::{Argon2, PasswordHasher};
+use argon2::{Algorithm, Argon2, Params, PasswordHasher, Version};
+use sha2::{Digest, Sha256};
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroize;
+use crate::models::structs::{KdfAlgorithm, KdfParams, KdfVersion};
 use crate::models::types::EncryptionKey;
 
 
-pub fn derive_key(password: &str, salt: &[u8]) -> Result<EncryptionKey, String> {
-    let hashed = crate::encryption::encrypt::fibbil_hash(password);
+pub fn derive_key(
+    password: &str,
+    key_file: Option<&[u8]>,
+    salt: &[u8],
+) -> Result<EncryptionKey, String> {
+    derive_key_with_params(password, key_file, salt, &KdfParams::default())
+}
+
+// derive a key from the master password and, as a second factor, a key file:
+pub fn derive_key_with_params(
+    password: &str,
+    key_file: Option<&[u8]>,
+    salt: &[u8],
+    kdf: &KdfParams,
+) -> Result<EncryptionKey, String> {
+    let mut secret = match kdf.version {
+        KdfVersion::V1 => crate::encryption::encrypt::fibbil_hash(password).into_bytes(),
+        KdfVersion::V2 => {
+            // NFC takes at most three times the input, so the buffer never reallocates
+            let mut normalized = String::with_capacity(password.len() * 3);
+            normalized.extend(password.nfc());
+            normalized.into_bytes()
+        }
+    };
+
+    // The key file digest is appended so the password alone derives a different key
+    if let Some(contents) = key_file {
+        secret.extend_from_slice(&Sha256::digest(contents));
+    }
+
+    let key = derive_key_from_bytes(&secret, salt, kdf);
+    secret.zeroize();
+    key
+}
+
+// derive a key from raw secret bytes (key file contents, recovery codes):
+pub fn derive_key_from_bytes(
+    secret: &[u8],
+    salt: &[u8],
+    kdf: &KdfParams,
+) -> Result<EncryptionKey, String> {
     let mut key = [0u8; 32];
-    let argon2 = Argon2::default();
+    let argon2 = build_argon2(kdf)?;
 
     // Convert the raw salt bytes to a SaltString
     let salt_str = SaltString::encode_b64(salt).map_err(|e| format!("Invalid salt: {}", e))?;
 
     let hash = argon2
-        .hash_password(hashed.as_bytes(), &salt_str)
+        .hash_password(secret, &salt_str)
         .map_err(|e| format!("Failed to derive key: {}", e))?;
 
     key.copy_from_slice(&hash.hash.unwrap().as_bytes()[..32]);
 
-    Ok(key)
+    Ok(EncryptionKey::new(&mut key))
 }
 
+// build an Argon2 instance from the parameters stored in the vault header:
+fn build_argon2(kdf: &KdfParams) -> Result<Argon2<'static>, String> {
+    let algorithm = match kdf.algorithm {
+        KdfAlgorithm::Argon2id => Algorithm::Argon2id,
+        KdfAlgorithm::Argon2i => Algorithm::Argon2i,
+        KdfAlgorithm::Argon2d => Algorithm::Argon2d,
+    };
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, None)
+        .map_err(|e| format!("Invalid KDF parameters: {}", e))?;
+    Ok(Argon2::new(algorithm, Version::V0x13, params))
+}
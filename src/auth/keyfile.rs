@@ -0,0 +1,78 @@
+use rand::RngCore;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// File name of the key file created by `rsp keyfile generate` in the home directory.
+pub const DEFAULT_KEY_FILE_NAME: &str = ".rsp.key";
+
+// Number of random bytes in a generated key file
+const KEY_FILE_LEN: usize = 64;
+
+// Contents of the key file given with `--keyfile`, used as a second factor
+lazy_static::lazy_static! {
+    static ref ACTIVE_KEY_FILE: Mutex<Option<Vec<u8>>> = Mutex::new(None);
+}
+
+// `~/.rsp.key`, or the current directory when there is no home:
+pub fn default_key_file_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(DEFAULT_KEY_FILE_NAME)
+}
+
+// expand a leading `~/` the shell did not (e.g. `--keyfile=~/.rsp.key`):
+pub fn expand_key_file_path(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+// read a key file, refusing empty files that would add no secret:
+pub fn read_key_file(path: &Path) -> io::Result<Vec<u8>> {
+    let contents = fs::read(path).map_err(|e| {
+        io::Error::new(e.kind(), format!("Failed to read key file {}: {}", path.display(), e))
+    })?;
+    if contents.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Key file {} is empty", path.display()),
+        ));
+    }
+    Ok(contents)
+}
+
+// use the key file at `path` for the rest of this process:
+pub fn set_key_file(path: &str) -> io::Result<()> {
+    let contents = read_key_file(&expand_key_file_path(path))?;
+    let mut active = ACTIVE_KEY_FILE
+        .lock()
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    *active = Some(contents);
+    Ok(())
+}
+
+// contents of the key file given with `--keyfile`, if any:
+pub fn active_key_file() -> Option<Vec<u8>> {
+    ACTIVE_KEY_FILE.lock().ok().and_then(|active| active.clone())
+}
+
+// write a new random key file, never overwriting an existing one:
+pub fn generate_key_file(path: &Path) -> io::Result<()> {
+    let mut contents = [0u8; KEY_FILE_LEN];
+    rand::rng().fill_bytes(&mut contents);
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+        .map_err(|e| {
+            io::Error::new(e.kind(), format!("Failed to create key file {}: {}", path.display(), e))
+        })?;
+    file.write_all(&contents)?;
+    file.sync_all()
+}
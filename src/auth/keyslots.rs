@@ -0,0 +1,168 @@
+use rand::RngCore;
+
+use crate::auth::handler::{derive_key_from_bytes, derive_key_with_params};
+use crate::encryption::{decrypt::decrypt_data, encrypt::encrypt_data};
+use crate::models::structs::{KdfParams, KeySlot, KeySlotKind, VaultHeader};
+use crate::models::types::EncryptionKey;
+
+// Unambiguous alphabet for recovery codes (no 0/O, 1/I)
+const RECOVERY_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const RECOVERY_CODE_GROUPS: usize = 5;
+const RECOVERY_CODE_GROUP_LEN: usize = 5;
+
+/// A secret that can open one or more key slots.
+pub enum UnlockSecret<'a> {
+    /// Master password, optionally combined with a key file as second factor.
+    Password(&'a str, Option<&'a [u8]>),
+    KeyFile(&'a [u8]),
+    RecoveryCode(&'a str),
+}
+
+impl UnlockSecret<'_> {
+    pub fn kind(&self) -> KeySlotKind {
+        match self {
+            UnlockSecret::Password(..) => KeySlotKind::Password,
+            UnlockSecret::KeyFile(_) => KeySlotKind::KeyFile,
+            UnlockSecret::RecoveryCode(_) => KeySlotKind::RecoveryCode,
+        }
+    }
+
+    // whether a key file is mixed into a password:
+    fn uses_key_file(&self) -> bool {
+        matches!(self, UnlockSecret::Password(_, Some(_)))
+    }
+
+    // derive the key-encryption key for a slot:
+    fn derive(&self, salt: &[u8], kdf: &KdfParams) -> Result<EncryptionKey, String> {
+        match self {
+            UnlockSecret::Password(password, key_file) => {
+                derive_key_with_params(password, *key_file, salt, kdf)
+            }
+            UnlockSecret::KeyFile(contents) => derive_key_from_bytes(contents, salt, kdf),
+            UnlockSecret::RecoveryCode(code) => {
+                derive_key_from_bytes(normalize_recovery_code(code).as_bytes(), salt, kdf)
+            }
+        }
+    }
+}
+
+// fresh random key for encrypting vault entries:
+pub fn generate_data_key() -> EncryptionKey {
+    let mut key = [0u8; 32];
+    rand::rng().fill_bytes(&mut key);
+    EncryptionKey::new(&mut key)
+}
+
+// random recovery code such as `ABCDE-FGHJK-...`:
+pub fn generate_recovery_code() -> String {
+    let mut rng = rand::rng();
+    (0..RECOVERY_CODE_GROUPS)
+        .map(|_| {
+            (0..RECOVERY_CODE_GROUP_LEN)
+                .map(|_| {
+                    let i = (rng.next_u32() as usize) % RECOVERY_CODE_ALPHABET.len();
+                    RECOVERY_CODE_ALPHABET[i] as char
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// recovery codes are accepted regardless of case, dashes and spaces:
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+// wrap the data key with a key derived from `secret`:
+pub fn create_key_slot(
+    data_key: &EncryptionKey,
+    secret: &UnlockSecret,
+    label: &str,
+) -> Result<KeySlot, String> {
+    let salt = crate::data::data::generate_salt();
+    let kdf = KdfParams::default();
+    let kek = secret.derive(&salt, &kdf)?;
+    let (nonce, wrapped_key) = encrypt_data(data_key.expose(), &kek)?;
+
+    let mut id = [0u8; 4];
+    rand::rng().fill_bytes(&mut id);
+
+    Ok(KeySlot {
+        id: id.iter().map(|b| format!("{:02x}", b)).collect(),
+        kind: secret.kind(),
+        key_file: secret.uses_key_file(),
+        label: label.to_string(),
+        created: chrono::Utc::now().to_rfc3339(),
+        kdf,
+        salt,
+        nonce,
+        wrapped_key,
+    })
+}
+
+// try to unwrap the data key from a single slot:
+pub fn open_key_slot(slot: &KeySlot, secret: &UnlockSecret) -> Option<EncryptionKey> {
+    if slot.kind != secret.kind() || slot.key_file != secret.uses_key_file() {
+        return None;
+    }
+    let kek = secret.derive(&slot.salt, &slot.kdf).ok()?;
+    let unwrapped = decrypt_data(&slot.wrapped_key, &kek, &slot.nonce).ok()?;
+    EncryptionKey::try_from(unwrapped).ok()
+}
+
+// recover the key that decrypts the vault payload:
+pub fn unlock_header(header: &VaultHeader, secret: &UnlockSecret) -> Result<EncryptionKey, String> {
+    unlock_header_slot(header, secret).map(|(key, _)| key)
+}
+
+// like `unlock_header`, also telling which key slot opened (None for legacy vaults):
+pub fn unlock_header_slot(
+    header: &VaultHeader,
+    secret: &UnlockSecret,
+) -> Result<(EncryptionKey, Option<usize>), String> {
+    if header.is_legacy() {
+        // Older vaults: the password-derived key encrypts the payload directly
+        return match secret {
+            UnlockSecret::Password(_, Some(_)) => {
+                Err("This vault has no key slots yet; unlock it once without a key file to upgrade it".into())
+            }
+            UnlockSecret::Password(password, None) => {
+                let salt = header
+                    .salt
+                    .as_ref()
+                    .ok_or_else(|| "Vault has neither key slots nor a salt".to_string())?;
+                let kdf = header.kdf.clone().unwrap_or_else(KdfParams::legacy);
+                derive_key_with_params(password, None, salt, &kdf).map(|key| (key, None))
+            }
+            _ => Err("This vault can only be unlocked with the master password".into()),
+        };
+    }
+
+    header
+        .key_slots
+        .iter()
+        .enumerate()
+        .find_map(|(i, slot)| open_key_slot(slot, secret).map(|key| (key, Some(i))))
+        .ok_or_else(|| match secret {
+            // Point out a missing or unexpected second factor
+            UnlockSecret::Password(_, key_file)
+                if !header.key_slots.iter().any(|slot| {
+                    slot.kind == KeySlotKind::Password && slot.key_file == key_file.is_some()
+                }) =>
+            {
+                if key_file.is_some() {
+                    "No password slot of this vault uses a key file".to_string()
+                } else {
+                    "This vault requires a key file (--keyfile <path>)".to_string()
+                }
+            }
+            UnlockSecret::Password(_, Some(_)) => "Invalid master password or key file".to_string(),
+            UnlockSecret::Password(_, None) => "Invalid master password".to_string(),
+            UnlockSecret::KeyFile(_) => "Key file does not match any key slot".to_string(),
+            UnlockSecret::RecoveryCode(_) => "Invalid recovery code".to_string(),
+        })
+}
@@ -1 +1,3 @@
-pub mod handler;
\ No newline at end of file
+pub mod handler;
+pub mod keyfile;
+pub mod keyslots;
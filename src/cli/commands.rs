@@ -1,28 +1,85 @@
-use crate::data::data::{load_passwords, save_passwords};
-use crate::models::structs::{Metadata, PasswordEntry};
-use crate::state::key::{load_key, save_key};
-use crate::state::manager::STATE_MANAGER;
-use crate::PASSWORD_FILE_PATH;
+use crate::auth::keyfile::{
+    active_key_file, default_key_file_path, expand_key_file_path, generate_key_file, read_key_file,
+};
+use crate::auth::keyslots::{
+    create_key_slot, generate_recovery_code, unlock_header, unlock_header_slot, UnlockSecret,
+};
+use crate::config::settings::SETTINGS;
+use crate::data::backup::{
+    backups_with_other_keys, find_backup, get_backup_dir, list_backups, restore_backup,
+};
+use crate::data::folders::{is_in_folder, list_folders, normalize_folder, parse_tags, rename_folder};
+use crate::data::trash::{empty_trash, list_trash, move_to_trash, restore_from_trash};
+use crate::data::data::{
+    change_master_password, load_passwords_with_secret, read_vault_header, record_use,
+    update_key_slots, update_passwords,
+};
+use crate::models::kinds::{DetailField, EntryKind};
+use crate::models::secret::SecretString;
+use crate::models::structs::{KeySlotKind, PasswordEntry, Timestamps};
+use crate::state::key::SESSION_ENV_VAR;
+use crate::state::manager::{AppState, STATE_MANAGER};
+use crate::utils::generator::GeneratorOptions;
+use crate::utils::verify_passwords::verify_password;
+use crate::data::vault::{
+    active_vault_name, find_legacy_vault, get_vault_path, legacy_vault_hint, list_vaults,
+};
+use crate::data::verify::verify_vault;
 use rpassword::read_password;
 use std::io::{self, Write};
+use std::path::PathBuf;
 
-pub fn execute_unlock(password_opt: Option<String>) -> io::Result<()> {
-    let password = match password_opt {
-        Some(p) => p,
+pub fn execute_unlock(password_opt: Option<String>, recovery: bool) -> io::Result<()> {
+    if let Some(legacy) = find_legacy_vault() {
+        println!("{}", legacy_vault_hint(&legacy));
+        println!("Run 'rsp tui' from that directory to move it there.");
+        return Err(io::Error::new(io::ErrorKind::NotFound, "No vault at the new location"));
+    }
+
+    let key_file = active_key_file();
+
+    let secret_text = match password_opt {
+        Some(p) => SecretString::from(p),
         None => {
-            print!("Enter master password: ");
+            let prompt = if recovery {
+                "Enter recovery code: "
+            } else if key_file.is_some() {
+                "Enter master password (leave empty to use the key file alone): "
+            } else {
+                "Enter master password: "
+            };
+            print!("{}", prompt);
             std::io::stdout().flush()?;
-            read_password()?
+            SecretString::from(read_password()?)
         }
     };
 
-    // Unlock with provided password
-    match load_passwords(PASSWORD_FILE_PATH, &password) {
-        Ok((passwords, key, salt)) => {
-            // Save the key before unlocking to ensure it's available
-            save_key(&password)?;
-            STATE_MANAGER.unlock(passwords, key, salt, Some(&password))?;
+    let secret = match key_file.as_deref() {
+        _ if recovery => UnlockSecret::RecoveryCode(&secret_text),
+        Some(contents) if secret_text.is_empty() => UnlockSecret::KeyFile(contents),
+        key_file => UnlockSecret::Password(&secret_text, key_file),
+    };
+
+    // Unlock with the provided secret
+    match load_passwords_with_secret(&get_vault_path(), &secret) {
+        Ok((passwords, key, header)) => {
+            let token = STATE_MANAGER.unlock(passwords, key, header)?;
             println!("Password store unlocked");
+            if let Some(token) = token {
+                println!();
+                println!("To use this session in your shell, run:");
+                println!("  export {}=\"{}\"", SESSION_ENV_VAR, token);
+            }
+            let timeout = SETTINGS.session_timeout_secs;
+            if timeout > 0 {
+                // Whole minutes read better; anything else is shown exactly
+                let expires = if timeout.is_multiple_of(60) {
+                    format!("{} minute{}", timeout / 60, if timeout == 60 { "" } else { "s" })
+                } else {
+                    format!("{} seconds", timeout)
+                };
+                println!("The session expires in {} or on 'rsp lock'.", expires);
+            }
             Ok(())
         }
         Err(e) => {
@@ -38,123 +95,857 @@ pub fn execute_lock() -> io::Result<()> {
     Ok(())
 }
 
-pub fn execute_add(name: String, password: String) -> io::Result<()> {
+pub fn execute_add(
+    kind: EntryKind,
+    name: String,
+    username: String,
+    password: SecretString,
+    folder: String,
+    tags: Vec<String>,
+    generate: Option<GeneratorOptions>,
+) -> io::Result<()> {
+    // Card numbers and keys have a format of their own, so only free-form secrets are generated
+    if generate.is_some() && !matches!(kind, EntryKind::Login | EntryKind::ApiCredential) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} entries have no secret that can be generated", kind.label()),
+        ));
+    }
+
     STATE_MANAGER.ensure_unlocked()?;
 
     let mut state = STATE_MANAGER.get_state()?;
 
     // Create a new password entry
-    let new_entry = PasswordEntry {
-        id: uuid::Uuid::new_v4().to_string(),
-        name,
-        password,
-        metadata: Metadata::default(),
-    };
-
-    state.passwords.push(new_entry);
+    let mut new_entry = PasswordEntry::new(name, username, password);
+    new_entry.kind = kind;
+    new_entry.folder = normalize_folder(&folder);
+    new_entry.tags = parse_tags(&tags.join(","));
+    if let Some(options) = &generate {
+        let secret = options
+            .generate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        new_entry.set_primary_secret(secret);
+    }
+    if kind == EntryKind::SecureNote {
+        new_entry.metadata.notes = Some(prompt_line("Note: ")?);
+    }
+    for field in kind.fields() {
+        if generate.is_some() && kind.primary_field() == Some(field.key) {
+            continue;
+        }
+        let value = prompt_detail(field)?;
+        if !value.is_empty() {
+            new_entry.details.insert(field.key.to_string(), value);
+        }
+    }
+    let warning = verify_password(&new_entry)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
 
-    // Save the updated passwords
-    save_passwords(
-        PASSWORD_FILE_PATH,
-        &state.passwords,
+    // Add to the vault as it is on disk now, so concurrent changes are kept
+    let (passwords, ()) = update_passwords(
+        &get_vault_path(),
         &state.encryption_key,
-        &state.salt,
+        &mut state.header,
+        |passwords| {
+            passwords.push(new_entry);
+            Ok(())
+        },
     )
     .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-
-    // Load cached password to avoid prompting
-    let cached_password = load_key().map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            format!("Failed to load key: {}. Please unlock again.", e),
-        )
-    })?;
-
-    // Update the state without prompting
-    STATE_MANAGER.unlock(
-        state.passwords,
-        state.encryption_key,
-        state.salt,
-        Some(&cached_password),
-    )?;
+    log::debug!("Vault now holds {} entries", passwords.len());
 
     println!("Password added successfully");
+    if let Some(warning) = warning {
+        println!("Warning: {}", warning);
+    }
+    if generate.is_some() {
+        println!("Use `rsp get` to copy the generated secret");
+    }
+    Ok(())
+}
+
+pub fn execute_generate(options: GeneratorOptions) -> io::Result<()> {
+    let secret = options
+        .generate()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    println!("{}", secret.expose());
     Ok(())
 }
 
-pub fn execute_list() -> io::Result<()> {
+fn prompt_line(prompt: &str) -> io::Result<String> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+// ask for one of the fields of an entry kind; secrets are not echoed and values that
+// span several lines, like a private key, are read from a file:
+fn prompt_detail(field: &DetailField) -> io::Result<SecretString> {
+    let optional = if field.required { "" } else { " (optional)" };
+    if field.multiline {
+        let path = prompt_line(&format!("Path to file with {}{}: ", field.label.to_lowercase(), optional))?;
+        if path.trim().is_empty() {
+            return Ok(SecretString::new());
+        }
+        let contents = std::fs::read_to_string(path.trim())?;
+        return Ok(SecretString::from(contents.trim_end().to_string()));
+    }
+    if field.kind.is_secret() {
+        print!("{}{}: ", field.label, optional);
+        io::stdout().flush()?;
+        return Ok(SecretString::from(read_password()?));
+    }
+    prompt_line(&format!("{}{}: ", field.label, optional)).map(SecretString::from)
+}
+
+/// Order of `rsp list`; times sort oldest first, with unknown ones ahead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListSort {
+    Name,
+    Created,
+    Modified,
+    PasswordChanged,
+    LastUsed,
+}
+
+impl std::str::FromStr for ListSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(ListSort::Name),
+            "created" => Ok(ListSort::Created),
+            "modified" => Ok(ListSort::Modified),
+            "changed" | "password-changed" => Ok(ListSort::PasswordChanged),
+            "used" | "last-used" => Ok(ListSort::LastUsed),
+            _ => Err(format!(
+                "Unknown sort '{}' (expected one of: name, created, modified, changed, used)",
+                s
+            )),
+        }
+    }
+}
+
+impl ListSort {
+    // the time an entry is sorted on, with the label it is printed under:
+    fn time<'a>(&self, entry: &'a PasswordEntry) -> Option<(&'static str, &'a str)> {
+        match self {
+            ListSort::Name => None,
+            ListSort::Created => Some(("created", &entry.times.created)),
+            ListSort::Modified => Some(("modified", &entry.times.modified)),
+            ListSort::PasswordChanged => Some(("changed", &entry.times.password_changed)),
+            ListSort::LastUsed => Some(("used", &entry.times.last_used)),
+        }
+    }
+}
+
+pub fn execute_list(
+    folder: Option<String>,
+    tag: Option<String>,
+    sort: Option<ListSort>,
+    older_than: Option<u64>,
+) -> io::Result<()> {
     STATE_MANAGER.ensure_unlocked()?;
 
     let state = STATE_MANAGER.get_state()?;
 
-    if state.passwords.is_empty() {
+    // `--folder` includes subfolders, `--tag` ignores case
+    let folder = folder.map(|folder| normalize_folder(&folder));
+    let mut entries: Vec<&PasswordEntry> = state
+        .passwords
+        .iter()
+        .filter(|entry| !entry.is_trashed())
+        .filter(|entry| folder.as_deref().is_none_or(|folder| is_in_folder(&entry.folder, folder)))
+        .filter(|entry| {
+            tag.as_deref()
+                .is_none_or(|tag| entry.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+        })
+        // `--older-than 0` lists every entry with a secret, as the age is in whole days
+        .filter(|entry| {
+            older_than.is_none_or(|days| {
+                entry.primary_secret().is_some()
+                    && entry.password_age_days().is_none_or(|age| age >= days as i64)
+            })
+        })
+        .collect();
+    match sort {
+        Some(ListSort::Name) => entries.sort_by_key(|entry| entry.name.to_lowercase()),
+        Some(sort) => entries.sort_by_key(|entry| sort.time(entry).map(|(_, time)| time)),
+        None => {}
+    }
+
+    if entries.is_empty() {
         println!("No passwords found");
         return Ok(());
     }
 
     println!("Passwords:");
-    for (i, entry) in state.passwords.iter().enumerate() {
-        println!("{}: {}", i + 1, entry.name);
+    for (i, entry) in entries.iter().enumerate() {
+        let mut line = format!("{}: {}", i + 1, entry.name);
+        if !entry.username.is_empty() {
+            line.push_str(&format!(" ({})", entry.username));
+        }
+        if !entry.folder.is_empty() {
+            line.push_str(&format!(" [{}]", entry.folder));
+        }
+        for tag in &entry.tags {
+            line.push_str(&format!(" #{}", tag));
+        }
+        if let Some((label, time)) = sort.and_then(|sort| sort.time(entry)) {
+            line.push_str(&format!(" - {} {}", label, Timestamps::display(time)));
+        }
+        if entry.is_password_stale(SETTINGS.password_max_age_days) || older_than.is_some() {
+            match entry.password_age_days() {
+                Some(days) => line.push_str(&format!(" ! password {} days old", days)),
+                None => line.push_str(" ! password age unknown"),
+            }
+        }
+        println!("{}", line);
     }
 
     Ok(())
 }
 
-pub fn execute_remove(name: String) -> io::Result<()> {
+// print the password of an entry, or any other field of it, for use in scripts:
+pub fn execute_get(name: String, field: Option<String>) -> io::Result<()> {
     STATE_MANAGER.ensure_unlocked()?;
 
     let mut state = STATE_MANAGER.get_state()?;
 
-    let initial_count = state.passwords.len();
-    state.passwords.retain(|entry| entry.name != name);
+    let entry = find_entry(&state.passwords, &name)?;
 
-    if state.passwords.len() == initial_count {
-        return Err(io::Error::new(
+    // Without --field, print what the kind is about: the password, card number, key...
+    let Some(field) = field else {
+        let value = entry.primary_secret().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("'{}' has nothing to print; name a field with --field", name),
+            )
+        })?;
+        println!("{}", value);
+        let id = entry.id.clone();
+        mark_used(&mut state, &id);
+        return Ok(());
+    };
+    let value = entry.field_value(&field).ok_or_else(|| {
+        let mut available = vec!["password", "username", "url", "notes"];
+        available.extend(entry.kind.fields().iter().map(|detail| detail.key));
+        available.extend(entry.metadata.fields.iter().map(|custom| custom.name.as_str()));
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("'{}' has no field '{}' (available: {})", name, field, available.join(", ")),
+        )
+    })?;
+    println!("{}", value);
+    let id = entry.id.clone();
+    mark_used(&mut state, &id);
+    Ok(())
+}
+
+// the only live entry named `name`:
+fn find_entry<'a>(passwords: &'a [PasswordEntry], name: &str) -> io::Result<&'a PasswordEntry> {
+    let mut matches = passwords.iter().filter(|entry| entry.name == name && !entry.is_trashed());
+    let entry = matches.next().ok_or_else(|| {
+        io::Error::new(
             io::ErrorKind::NotFound,
             format!("Password with name '{}' not found", name),
+        )
+    })?;
+    if matches.next().is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Several entries are named '{}'", name),
         ));
     }
+    Ok(entry)
+}
+
+// List the earlier passwords of an entry, masked unless `show` is set, or bring one
+// back with `restore` (1 being the newest)
+pub fn execute_history(name: String, show: bool, restore: Option<usize>) -> io::Result<()> {
+    STATE_MANAGER.ensure_unlocked()?;
+
+    let mut state = STATE_MANAGER.get_state()?;
+    let entry = find_entry(&state.passwords, &name)?;
+
+    if let Some(position) = restore {
+        let id = entry.id.clone();
+        update_passwords(
+            &get_vault_path(),
+            &state.encryption_key,
+            &mut state.header,
+            |passwords| {
+                passwords
+                    .iter_mut()
+                    .find(|entry| entry.id == id)
+                    .ok_or("Entry was removed by another process")?
+                    .restore_from_history(position - 1, SETTINGS.password_history_limit)
+            },
+        )
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        println!("Password #{} of {} restored", position, name);
+        return Ok(());
+    }
+
+    if entry.history.is_empty() {
+        println!("No earlier passwords for {}", name);
+        return Ok(());
+    }
+    for (i, old) in entry.history.iter().enumerate() {
+        let value = if show { old.value.expose() } else { "••••••••" };
+        println!("{}: replaced {}  {}", i + 1, Timestamps::display(&old.replaced), value);
+    }
+    Ok(())
+}
+
+// Stamp an entry as used after printing it. The value is already out, so a failure
+// is only logged
+fn mark_used(state: &mut AppState, id: &str) {
+    if let Err(e) = record_use(&get_vault_path(), &state.encryption_key, &mut state.header, id) {
+        log::warn!("Failed to record use of entry: {}", e);
+    }
+}
+
+pub fn execute_folder_list() -> io::Result<()> {
+    STATE_MANAGER.ensure_unlocked()?;
+
+    let state = STATE_MANAGER.get_state()?;
+    let folders = list_folders(&state.passwords);
+    if folders.is_empty() {
+        println!("No folders found");
+        return Ok(());
+    }
+
+    for folder in folders {
+        let count = state
+            .passwords
+            .iter()
+            .filter(|entry| !entry.is_trashed() && is_in_folder(&entry.folder, &folder))
+            .count();
+        let depth = folder.matches('/').count();
+        let name = folder.rsplit('/').next().unwrap_or(&folder);
+        println!("{}{}/ ({})", "  ".repeat(depth), name, count);
+    }
+    Ok(())
+}
 
-    // Save the updated passwords
-    save_passwords(
-        PASSWORD_FILE_PATH,
-        &state.passwords,
+// move or rename a folder and everything below it in a single save:
+pub fn execute_folder_rename(from: String, to: String) -> io::Result<()> {
+    STATE_MANAGER.ensure_unlocked()?;
+
+    let mut state = STATE_MANAGER.get_state()?;
+
+    let (_, moved) = update_passwords(
+        &get_vault_path(),
         &state.encryption_key,
-        &state.salt,
+        &mut state.header,
+        |passwords| rename_folder(passwords, &from, &to),
     )
     .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
 
-    // Load cached password to avoid prompting
-    let cached_password = load_key().map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            format!("Failed to load key: {}. Please unlock again.", e),
-        )
-    })?;
+    println!("Moved {} entries from '{}' to '{}'", moved, normalize_folder(&from), normalize_folder(&to));
+    Ok(())
+}
+
+pub fn execute_remove(name: String) -> io::Result<()> {
+    STATE_MANAGER.ensure_unlocked()?;
+
+    let mut state = STATE_MANAGER.get_state()?;
+    // Only the one entry `rsp get` would pick; ambiguous names are refused
+    let id = find_entry(&state.passwords, &name)?.id.clone();
+
+    // Move to the trash in the vault as it is on disk now, so concurrent changes are kept
+    let (passwords, ()) = update_passwords(
+        &get_vault_path(),
+        &state.encryption_key,
+        &mut state.header,
+        |passwords| {
+            if move_to_trash(passwords, |entry| entry.id == id) == 0 {
+                return Err(format!("{} was removed by another process", name));
+            }
+            Ok(())
+        },
+    )
+    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    log::debug!("Vault now holds {} entries", passwords.len());
+
+    println!("Password moved to the trash (rsp trash restore '{}' brings it back)", name);
+    Ok(())
+}
+
+pub fn execute_trash_list() -> io::Result<()> {
+    STATE_MANAGER.ensure_unlocked()?;
+
+    let state = STATE_MANAGER.get_state()?;
+    let trash = list_trash(&state.passwords);
+    if trash.is_empty() {
+        println!("The trash is empty");
+        return Ok(());
+    }
+
+    for (i, entry) in trash.iter().enumerate() {
+        println!("{}: {} (deleted {})", i + 1, entry.name, Timestamps::display(&entry.times.deleted));
+    }
+    if SETTINGS.trash_retention_days > 0 {
+        println!();
+        println!("Entries are deleted for good {} days after they were trashed.", SETTINGS.trash_retention_days);
+    }
+    Ok(())
+}
+
+// Restore a trashed entry, named by its number in `rsp trash list` or by its name
+pub fn execute_trash_restore(entry: String) -> io::Result<()> {
+    STATE_MANAGER.ensure_unlocked()?;
+
+    let mut state = STATE_MANAGER.get_state()?;
+    let trash = list_trash(&state.passwords);
+    let by_number = entry
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|i| trash.get(i));
+    let found = match by_number {
+        Some(found) => found,
+        None => {
+            let mut matches = trash.iter().filter(|trashed| trashed.name == entry);
+            let found = matches.next().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, format!("'{}' is not in the trash", entry))
+            })?;
+            if matches.next().is_some() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Several trashed entries are named '{}'; use the number from rsp trash list", entry),
+                ));
+            }
+            found
+        }
+    };
+    let (id, name) = (found.id.clone(), found.name.clone());
+
+    update_passwords(
+        &get_vault_path(),
+        &state.encryption_key,
+        &mut state.header,
+        |passwords| restore_from_trash(passwords, &id),
+    )
+    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+    println!("{} restored", name);
+    Ok(())
+}
+
+pub fn execute_trash_empty() -> io::Result<()> {
+    STATE_MANAGER.ensure_unlocked()?;
+
+    let mut state = STATE_MANAGER.get_state()?;
+    let (_, purged) = update_passwords(
+        &get_vault_path(),
+        &state.encryption_key,
+        &mut state.header,
+        |passwords| Ok(empty_trash(passwords)),
+    )
+    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+    println!("Deleted {} entries for good", purged);
+    Ok(())
+}
+
+pub fn execute_passwd() -> io::Result<()> {
+    print!("Enter current master password: ");
+    std::io::stdout().flush()?;
+    let old_password = SecretString::from(read_password()?);
+
+    print!("Enter new master password: ");
+    std::io::stdout().flush()?;
+    let new_password = SecretString::from(read_password()?);
+
+    print!("Confirm new master password: ");
+    std::io::stdout().flush()?;
+    let confirm_password = SecretString::from(read_password()?);
+
+    if new_password.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "New master password cannot be empty",
+        ));
+    }
+    if new_password != confirm_password {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "New passwords do not match",
+        ));
+    }
+
+    // The vault gets a new data key; every other key slot needs its secret to move to it
+    let key_file = active_key_file();
+    let header = read_vault_header(&get_vault_path())
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    let (_, opened) = unlock_header_slot(&header, &UnlockSecret::Password(&old_password, key_file.as_deref()))
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Current master password is incorrect"))?;
+
+    let mut other_passwords: Vec<(SecretString, Option<Vec<u8>>)> = Vec::new();
+    let mut other_key_files: Vec<Vec<u8>> = Vec::new();
+    let mut recovery_codes: Vec<SecretString> = Vec::new();
+    for (i, slot) in header.key_slots.iter().enumerate() {
+        if Some(i) == opened {
+            continue;
+        }
+        println!("Key slot {} ({}) has to be moved to the new vault key.", slot.id, slot.label);
+        match slot.kind {
+            KeySlotKind::Password => {
+                print!("Enter its password: ");
+                std::io::stdout().flush()?;
+                let password = SecretString::from(read_password()?);
+                let slot_key_file = match (slot.key_file, &key_file) {
+                    (false, _) => None,
+                    (true, Some(contents)) => Some(contents.clone()),
+                    (true, None) => {
+                        let path = prompt_line("Path to its key file: ")?;
+                        Some(read_key_file(&expand_key_file_path(path.trim()))?)
+                    }
+                };
+                other_passwords.push((password, slot_key_file));
+            }
+            KeySlotKind::KeyFile => {
+                let path = prompt_line("Path to the key file: ")?;
+                other_key_files.push(read_key_file(&expand_key_file_path(path.trim()))?);
+            }
+            KeySlotKind::RecoveryCode => {
+                print!("Enter the recovery code: ");
+                std::io::stdout().flush()?;
+                recovery_codes.push(SecretString::from(read_password()?));
+            }
+        }
+    }
+    let other_secrets: Vec<UnlockSecret> = other_passwords
+        .iter()
+        .map(|(password, slot_key_file)| UnlockSecret::Password(password, slot_key_file.as_deref()))
+        .chain(other_key_files.iter().map(|contents| UnlockSecret::KeyFile(contents)))
+        .chain(recovery_codes.iter().map(|code| UnlockSecret::RecoveryCode(code)))
+        .collect();
+
+    let (_, key, _) = change_master_password(
+        &get_vault_path(),
+        &old_password,
+        &new_password,
+        key_file.as_deref(),
+        &other_secrets,
+    )
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    // Cached credentials were derived from the old password
+    STATE_MANAGER.invalidate()?;
+
+    println!("Master password changed. Run 'rsp unlock' to unlock with the new password.");
+
+    // Backups under the old key were moved to the new one; older ones can't be
+    let exposed = backups_with_other_keys(&get_vault_path(), &key)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    if !exposed.is_empty() {
+        println!();
+        println!("These backups are encrypted with an earlier vault key and still open with its old passwords:");
+        for backup in &exposed {
+            println!("  {}  {}", backup.created_display(), backup.file_name);
+        }
+        let answer = prompt_line("Delete them? [y/N] ")?;
+        if answer.trim().eq_ignore_ascii_case("y") {
+            for backup in &exposed {
+                std::fs::remove_file(&backup.path)?;
+            }
+            println!("Deleted {} backups", exposed.len());
+        } else {
+            println!(
+                "Kept them; delete them from {} when they are no longer needed.",
+                get_backup_dir(&get_vault_path()).display()
+            );
+        }
+    }
+    Ok(())
+}
+
+pub fn execute_vault_list() -> io::Result<()> {
+    let vaults = list_vaults()?;
+    let active = active_vault_name();
 
-    // Update the state without prompting
-    STATE_MANAGER.unlock(
-        state.passwords,
-        state.encryption_key,
-        state.salt,
-        Some(&cached_password),
-    )?;
+    if vaults.is_empty() {
+        println!("No vaults found");
+        return Ok(());
+    }
+
+    println!("Vaults:");
+    for name in vaults {
+        let marker = if name == active { "*" } else { " " };
+        println!("{} {}", marker, name);
+    }
 
-    println!("Password removed successfully");
+    Ok(())
+}
+
+// Check a vault file (the active vault unless a path is given) for damage and tampering
+pub fn execute_vault_verify(path: Option<String>) -> io::Result<()> {
+    let file_path = path.map(PathBuf::from).unwrap_or_else(get_vault_path);
+    let key_file = active_key_file();
+
+    println!("Verifying {}", file_path.display());
+    let report = verify_vault(&file_path, |header| {
+        if key_file.is_some() {
+            print!("Enter master password (leave empty to use the key file alone): ");
+        } else {
+            print!("Enter master password: ");
+        }
+        std::io::stdout().flush().map_err(|e| e.to_string())?;
+        let password = SecretString::from(read_password().map_err(|e| e.to_string())?);
+
+        let secret = match key_file.as_deref() {
+            Some(contents) if password.is_empty() => UnlockSecret::KeyFile(contents),
+            key_file => UnlockSecret::Password(&password, key_file),
+        };
+        unlock_header(header, &secret)
+    });
+
+    for warning in &report.warnings {
+        println!("  warning: {}", warning);
+    }
+    for error in &report.errors {
+        println!("  error: {}", error);
+    }
+
+    match report.entries {
+        Some(entries) if report.is_ok() => {
+            println!("OK: {} entries, header and entries are intact", entries);
+            Ok(())
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Vault verification failed",
+        )),
+    }
+}
+
+pub fn execute_backup_list() -> io::Result<()> {
+    let backups = list_backups(&get_vault_path())?;
+
+    if backups.is_empty() {
+        println!("No backups found for vault '{}'", active_vault_name());
+        return Ok(());
+    }
+
+    println!("Backups of vault '{}' (newest first):", active_vault_name());
+    for (i, backup) in backups.iter().enumerate() {
+        println!(
+            "{}: {}  {} bytes  ({})",
+            i + 1,
+            backup.created_display(),
+            backup.size,
+            backup.file_name
+        );
+    }
+
+    Ok(())
+}
+
+pub fn execute_backup_restore(backup: String) -> io::Result<()> {
+    let vault_path = get_vault_path();
+    let backup = find_backup(&vault_path, &backup)
+        .map_err(|e| io::Error::new(io::ErrorKind::NotFound, e))?;
+
+    // The restored entries are saved again, which takes the backup's key
+    print!("Enter master password: ");
+    std::io::stdout().flush()?;
+    let password = SecretString::from(read_password()?);
+    let key_file = active_key_file();
+    let secret = UnlockSecret::Password(&password, key_file.as_deref());
+
+    restore_backup(&vault_path, &backup, &secret, SETTINGS.backup_count)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+    println!(
+        "Vault '{}' restored from backup taken {}",
+        active_vault_name(),
+        backup.created_display()
+    );
+    println!("The previous contents were saved as a new backup");
+    Ok(())
+}
+
+// human readable name of a key slot type:
+fn key_slot_kind_name(kind: KeySlotKind) -> &'static str {
+    match kind {
+        KeySlotKind::Password => "password",
+        KeySlotKind::KeyFile => "key file",
+        KeySlotKind::RecoveryCode => "recovery code",
+    }
+}
+
+pub fn execute_key_list() -> io::Result<()> {
+    let header = read_vault_header(&get_vault_path())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    if header.key_slots.is_empty() {
+        println!("Vault '{}' has no key slots yet; unlock it once to upgrade it", active_vault_name());
+        return Ok(());
+    }
+
+    println!("Key slots of vault '{}':", active_vault_name());
+    for slot in &header.key_slots {
+        println!(
+            "{}  {:<14} {}  (added {})",
+            slot.id,
+            key_slot_kind_name(slot.kind),
+            slot.label,
+            slot.created
+        );
+    }
+
+    Ok(())
+}
+
+pub fn execute_key_add(kind: KeySlotKind, keyfile: Option<String>) -> io::Result<()> {
+    STATE_MANAGER.ensure_unlocked()?;
+    let mut state = STATE_MANAGER.get_state()?;
+
+    let mut recovery_code = None;
+    let slot = match kind {
+        KeySlotKind::Password => {
+            print!("Enter new password: ");
+            std::io::stdout().flush()?;
+            let password = SecretString::from(read_password()?);
+            print!("Confirm new password: ");
+            std::io::stdout().flush()?;
+            let confirm = SecretString::from(read_password()?);
+            if password.is_empty() || password != confirm {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Passwords are empty or do not match",
+                ));
+            }
+            // With --keyfile the new password only works together with that key file
+            let key_file = active_key_file();
+            let label = if key_file.is_some() { "password + key file" } else { "password" };
+            create_key_slot(
+                &state.encryption_key,
+                &UnlockSecret::Password(&password, key_file.as_deref()),
+                label,
+            )
+        }
+        KeySlotKind::KeyFile => {
+            let path = keyfile.unwrap_or_default();
+            let contents = read_key_file(&expand_key_file_path(&path))?;
+            create_key_slot(&state.encryption_key, &UnlockSecret::KeyFile(&contents), &path)
+        }
+        KeySlotKind::RecoveryCode => {
+            let code = generate_recovery_code();
+            let slot = create_key_slot(
+                &state.encryption_key,
+                &UnlockSecret::RecoveryCode(&code),
+                "recovery code",
+            );
+            recovery_code = Some(code);
+            slot
+        }
+    }
+    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+    let id = slot.id.clone();
+    update_key_slots(&get_vault_path(), &state.encryption_key, &mut state.header, |slots| {
+        slots.push(slot);
+        Ok(())
+    })
+    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+    println!("Added {} key slot {}", key_slot_kind_name(kind), id);
+    if let Some(code) = recovery_code {
+        println!();
+        println!("Recovery code: {}", code);
+        println!("Write it down and keep it somewhere safe. It will not be shown again.");
+    }
+    Ok(())
+}
+
+pub fn execute_key_remove(id: String) -> io::Result<()> {
+    STATE_MANAGER.ensure_unlocked()?;
+    let mut state = STATE_MANAGER.get_state()?;
+
+    update_key_slots(&get_vault_path(), &state.encryption_key, &mut state.header, |slots| {
+        let initial_count = slots.len();
+        slots.retain(|slot| slot.id != id);
+        if slots.len() == initial_count {
+            return Err(format!("Key slot '{}' not found", id));
+        }
+        Ok(())
+    })
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    println!("Key slot {} removed", id);
+    Ok(())
+}
+
+pub fn execute_keyfile_generate(path: Option<String>) -> io::Result<()> {
+    let path = match path {
+        Some(path) => expand_key_file_path(&path),
+        None => default_key_file_path(),
+    };
+    generate_key_file(&path)?;
+
+    println!("Key file written to {}", path.display());
+    println!("Add it to your vault with 'rsp --keyfile {} key add password',", path.display());
+    println!("then remove the old password-only slot with 'rsp key remove <id>'.");
+    println!("Keep a copy somewhere safe: without it, slots that need it cannot be opened.");
     Ok(())
 }
 
 pub fn execute_help() -> io::Result<()> {
     println!("RustPass - Password Manager");
-    println!("Usage: rsp <command> [options]");
+    println!("Usage: rsp [--vault <name>] <command> [options]");
     println!("");
     println!("Commands:");
-    println!("  add <name> <password>              Add a new password entry");
-    println!("  list                               List all password entries");
-    println!("  remove <name>                      Remove a password entry");
+    println!("  add <name> <username> <password>   Add a new password entry");
+    println!("    --folder <path> --tag <tag>      Put it in a folder (e.g. work/aws) and tag it");
+    println!("  add --kind <kind> <name>           Add a note, card, identity, ssh-key or api-credential;");
+    println!("                                     its fields are prompted for");
+    println!("    --generate [generator options]   Generate the password (or API secret) instead");
+    println!("  generate                           Print a random password");
+    println!("    --length <n>                     Number of characters (default 20)");
+    println!("    --no-lowercase --no-uppercase --no-digits --no-symbols  Leave out a character class");
+    println!("    --no-ambiguous                   Leave out look-alikes such as l, 1, O and 0");
+    println!("    --pronounceable                  Alternate consonants and vowels");
+    println!("    --passphrase [--words <n>] [--separator <s>] [--capitalize]  Words instead (default 5, '-')");
+    println!("  list [--folder <path>] [--tag <t>] List password entries, optionally filtered");
+    println!("    --sort <name|created|modified|changed|used>  Sort, oldest first for times");
+    println!("    --older-than <days>              Only passwords not changed for that many days");
+    println!("  get <name> [--field <field>]       Print the password (card number, key...), or another field");
+    println!("  history <name> [--show]            List earlier passwords of an entry");
+    println!("    --restore <n>                    Make the n-th earlier password current again");
+    println!("  remove <name>                      Move a password entry to the trash");
+    println!("  trash list                         List deleted entries");
+    println!("  trash restore <n|name>             Bring a deleted entry back");
+    println!("  trash empty                        Delete everything in the trash for good");
+    println!("  folder list                        Show the folder tree");
+    println!("  folder rename <folder> <new path>  Move or rename a folder with everything in it");
     println!("  unlock [password]                  Unlock the password database");
+    println!("    --recovery                       Unlock with a recovery code");
+
     println!("  lock                               Lock the password database");
+    println!("  passwd                             Change the master password");
     println!("  tui                                Launch the terminal UI");
+    println!("  vault list                         List available vaults");
+    println!("  vault verify [file]                Check the vault (or a backup file) for damage and tampering");
+    println!("  backup list                        List backups of the vault");
+    println!("  backup restore <number|file>       Restore the vault from a backup");
+    println!("  key list                           List the key slots of the vault");
+    println!("  key add password                   Add another password that unlocks the vault");
+    println!("  key add keyfile <path>             Allow unlocking with a key file");
+    println!("  key add recovery                   Generate a one-time recovery code");
+    println!("  key remove <id>                    Remove a key slot");
+    println!("  keyfile generate [path]            Create a random key file (default: ~/.rsp.key)");
     println!("  help                               Show this help message");
+    println!();
+    println!("Options:");
+    println!("  --vault <name>                     Use the named vault (default: $RSP_VAULT or 'default')");
+    println!("  --keyfile <path>                   Use a key file as second factor, or alone with an empty password");
+    println!("  --accept-rollback                  Open a vault older than the last revision seen (e.g. after a bad sync)");
     Ok(())
 }
@@ -4,35 +4,218 @@ use std::path::PathBuf;
 
 use crate::cli::commands::{
     execute_add,
+    execute_backup_list,
+    execute_backup_restore,
+    execute_folder_list,
+    execute_folder_rename,
+    execute_generate,
     execute_help,
+    execute_key_add,
+    execute_keyfile_generate,
+    execute_key_list,
+    execute_key_remove,
     execute_list,
+    execute_get,
+    execute_history,
     execute_lock,
+    execute_passwd,
     execute_remove,
+    execute_trash_empty,
+    execute_trash_list,
+    execute_trash_restore,
     execute_unlock,
+    execute_vault_list,
+    execute_vault_verify,
+    ListSort,
 };
 use crate::daemon::{ service::DaemonService, client::DaemonClient };
+use crate::models::kinds::EntryKind;
+use crate::models::secret::SecretString;
+use crate::models::structs::KeySlotKind;
+use crate::utils::generator::{ GeneratorOptions, PassphraseOptions, PasswordOptions };
+use crate::utils::logger::{ get_log_path, DAEMON_LOG_FILE_NAME };
 
 pub enum Command {
     Add {
+        kind: EntryKind,
         name: String,
-        password: String,
+        username: String,
+        password: SecretString,
+        folder: String,
+        tags: Vec<String>,
+        generate: Option<GeneratorOptions>,
     },
-    List,
+    Generate {
+        options: GeneratorOptions,
+    },
+    List {
+        folder: Option<String>,
+        tag: Option<String>,
+        sort: Option<ListSort>,
+        older_than: Option<u64>,
+    },
+    Get {
+        name: String,
+        field: Option<String>,
+    },
+    History {
+        name: String,
+        show: bool,
+        restore: Option<usize>,
+    },
+    TrashList,
+    TrashRestore {
+        entry: String,
+    },
+    TrashEmpty,
     Remove {
         name: String,
     },
     Unlock {
         password: Option<String>,
+        recovery: bool,
     },
     Lock,
+    Passwd,
     Help,
     Tui,
     StartDaemon,
     StopDaemon,
     DaemonStatus,
+    VaultList,
+    VaultVerify {
+        path: Option<String>,
+    },
+    BackupList,
+    BackupRestore {
+        backup: String,
+    },
+    KeyList,
+    KeyAdd {
+        kind: KeySlotKind,
+        keyfile: Option<String>,
+    },
+    KeyRemove {
+        id: String,
+    },
+    KeyFileGenerate {
+        path: Option<String>,
+    },
+    FolderList,
+    FolderRename {
+        from: String,
+        to: String,
+    },
     Invalid,
 }
 
+// options accepted before or after any command:
+#[derive(Default)]
+pub struct GlobalOptions {
+    pub vault: Option<String>,
+    pub keyfile: Option<String>,
+    pub accept_rollback: bool,
+}
+
+// remove global options from the arguments so commands only see their own:
+pub fn extract_global_options(args: &mut Vec<String>) -> Result<GlobalOptions, String> {
+    let mut options = GlobalOptions::default();
+    let mut i = 1;
+    while i < args.len() {
+        if args[i] == "--vault" {
+            if i + 1 >= args.len() {
+                return Err("Missing value for --vault".into());
+            }
+            options.vault = Some(args[i + 1].clone());
+            args.drain(i..i + 2);
+        } else if let Some(name) = args[i].strip_prefix("--vault=") {
+            options.vault = Some(name.to_string());
+            args.remove(i);
+        } else if args[i] == "--keyfile" {
+            if i + 1 >= args.len() {
+                return Err("Missing value for --keyfile".into());
+            }
+            options.keyfile = Some(args[i + 1].clone());
+            args.drain(i..i + 2);
+        } else if let Some(path) = args[i].strip_prefix("--keyfile=") {
+            options.keyfile = Some(path.to_string());
+            args.remove(i);
+        } else if args[i] == "--accept-rollback" {
+            options.accept_rollback = true;
+            args.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    Ok(options)
+}
+
+// positional arguments of a command, and the `(name, value)` pairs of its options
+type SplitArgs = (Vec<String>, Vec<(String, String)>);
+
+// split command arguments into positional ones and `--name value` / `--name=value`
+// pairs for the options in `names`:
+fn split_options(args: &[String], names: &[&str]) -> Result<SplitArgs, String> {
+    let mut positional = Vec::new();
+    let mut options = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if let Some((name, value)) = arg.split_once('=').filter(|(name, _)| names.contains(name)) {
+            options.push((name.to_string(), value.to_string()));
+        } else if names.contains(&arg.as_str()) {
+            let value = iter.next().ok_or_else(|| format!("Missing value for {}", arg))?;
+            options.push((arg.clone(), value.clone()));
+        } else {
+            positional.push(arg.clone());
+        }
+    }
+    Ok((positional, options))
+}
+
+const GENERATOR_USAGE: &str = "[--length <n>] [--no-lowercase] [--no-uppercase] [--no-digits] [--no-symbols] [--no-ambiguous] [--pronounceable]\n       [--passphrase [--words <n>] [--separator <s>] [--capitalize]]";
+
+// take the generator flags out of the arguments, leaving the rest to the command:
+fn parse_generator_options(args: &[String]) -> Result<(GeneratorOptions, Vec<String>), String> {
+    let (rest, values) = split_options(args, &["--length", "--words", "--separator"])?;
+    let mut password = PasswordOptions::default();
+    let mut passphrase = PassphraseOptions::default();
+    let mut use_passphrase = false;
+    for (name, value) in values {
+        let number = || {
+            value
+                .parse::<usize>()
+                .map_err(|_| format!("{} takes a number", name))
+        };
+        match name.as_str() {
+            "--length" => password.length = number()?,
+            "--words" => passphrase.words = number()?,
+            _ => passphrase.separator = value.clone(),
+        }
+    }
+
+    let mut remaining = Vec::new();
+    for arg in rest {
+        match arg.as_str() {
+            "--no-lowercase" => password.lowercase = false,
+            "--no-uppercase" => password.uppercase = false,
+            "--no-digits" => password.digits = false,
+            "--no-symbols" => password.symbols = false,
+            "--no-ambiguous" => password.exclude_ambiguous = true,
+            "--pronounceable" => password.pronounceable = true,
+            "--passphrase" => use_passphrase = true,
+            "--capitalize" => passphrase.capitalize = true,
+            _ => remaining.push(arg),
+        }
+    }
+
+    let options = if use_passphrase {
+        GeneratorOptions::Passphrase(passphrase)
+    } else {
+        GeneratorOptions::Password(password)
+    };
+    Ok((options, remaining))
+}
+
 pub fn parse_args(args: Vec<String>) -> Command {
     if args.len() < 2 {
         return Command::Invalid;
@@ -40,17 +223,190 @@ pub fn parse_args(args: Vec<String>) -> Command {
 
     match args[1].as_str() {
         "add" => {
-            if args.len() < 5 {
+            let usage = "Usage: rsp add <name> <username> <password> [--folder <path>] [--tag <tag>]...\n       rsp add <name> <username> --generate [generator options]\n       rsp add --kind <kind> <name> [--generate [generator options]] [--folder <path>] [--tag <tag>]...";
+            let mut rest: Vec<String> = args[2..].to_vec();
+            let generate = match rest.iter().position(|arg| arg == "--generate") {
+                None => None,
+                Some(i) => {
+                    rest.remove(i);
+                    match parse_generator_options(&rest) {
+                        Ok((options, remaining)) => {
+                            rest = remaining;
+                            Some(options)
+                        }
+                        Err(e) => {
+                            println!("{}", e);
+                            println!("{}", usage);
+                            return Command::Invalid;
+                        }
+                    }
+                }
+            };
+            let (positional, options) = match split_options(&rest, &["--kind", "--folder", "--tag"]) {
+                Ok(split) => split,
+                Err(e) => {
+                    println!("{}", e);
+                    println!("{}", usage);
+                    return Command::Invalid;
+                }
+            };
+            let values = |name: &str| -> Vec<String> {
+                options.iter().filter(|(n, _)| n == name).map(|(_, v)| v.clone()).collect()
+            };
+            let kind = match values("--kind").pop().map(|kind| kind.parse::<EntryKind>()) {
+                None => EntryKind::Login,
+                Some(Ok(kind)) => kind,
+                Some(Err(e)) => {
+                    println!("{}", e);
+                    return Command::Invalid;
+                }
+            };
+            // Only logins take their secret on the command line; other kinds are prompted for
+            let needed = match kind {
+                EntryKind::Login if generate.is_none() => 3,
+                EntryKind::Login => 2,
+                _ => 1,
+            };
+            if positional.len() < needed {
                 println!("Not enough arguments for add command");
-                println!("Usage: rsp add <name> <username> <password>");
+                println!("{}", usage);
+                return Command::Invalid;
+            }
+            // e.g. a password next to --generate, which would otherwise be dropped
+            if positional.len() > needed {
+                println!("Too many arguments for add command");
+                println!("{}", usage);
                 return Command::Invalid;
             }
             Command::Add {
-                name: args[2].clone(),
-                password: args[4].clone(),
+                kind,
+                name: positional[0].clone(),
+                username: positional.get(1).cloned().unwrap_or_default(),
+                password: positional.get(2).map(|p| p.as_str().into()).unwrap_or_default(),
+                folder: values("--folder").pop().unwrap_or_default(),
+                tags: values("--tag"),
+                generate,
             }
         }
-        "list" => Command::List,
+        "generate" => match parse_generator_options(&args[2..]) {
+            Ok((options, remaining)) if remaining.is_empty() => Command::Generate { options },
+            Ok((_, remaining)) => {
+                println!("Unknown option for generate: {}", remaining[0]);
+                println!("Usage: rsp generate {}", GENERATOR_USAGE);
+                Command::Invalid
+            }
+            Err(e) => {
+                println!("{}", e);
+                println!("Usage: rsp generate {}", GENERATOR_USAGE);
+                Command::Invalid
+            }
+        },
+        "list" => {
+            let usage = "Usage: rsp list [--folder <path>] [--tag <tag>] [--sort <name|created|modified|changed|used>] [--older-than <days>]";
+            let (_, options) = match split_options(&args[2..], &["--folder", "--tag", "--sort", "--older-than"]) {
+                Ok(split) => split,
+                Err(e) => {
+                    println!("{}", e);
+                    println!("{}", usage);
+                    return Command::Invalid;
+                }
+            };
+            let option = |name: &str| {
+                options.iter().rev().find(|(n, _)| n == name).map(|(_, v)| v.clone())
+            };
+            let sort = match option("--sort").map(|sort| sort.parse::<ListSort>()).transpose() {
+                Ok(sort) => sort,
+                Err(e) => {
+                    println!("{}", e);
+                    return Command::Invalid;
+                }
+            };
+            let older_than = match option("--older-than").map(|days| days.parse::<u64>()).transpose() {
+                Ok(days) => days,
+                Err(_) => {
+                    println!("--older-than takes a number of days");
+                    println!("{}", usage);
+                    return Command::Invalid;
+                }
+            };
+            Command::List {
+                folder: option("--folder"),
+                tag: option("--tag"),
+                sort,
+                older_than,
+            }
+        }
+        "trash" => match args.get(2).map(String::as_str) {
+            None | Some("list") => Command::TrashList,
+            Some("restore") if args.len() >= 4 => Command::TrashRestore { entry: args[3].clone() },
+            Some("empty") => Command::TrashEmpty,
+            _ => {
+                println!("Usage: rsp trash [list|restore <n|name>|empty]");
+                Command::Invalid
+            }
+        },
+        "folder" => match args.get(2).map(String::as_str) {
+            Some("list") => Command::FolderList,
+            Some("rename") | Some("move") if args.len() >= 5 => Command::FolderRename {
+                from: args[3].clone(),
+                to: args[4].clone(),
+            },
+            _ => {
+                println!("Usage: rsp folder [list|rename <folder> <new path>]");
+                Command::Invalid
+            }
+        },
+        "get" => {
+            let mut name = None;
+            let mut field = None;
+            let mut rest = args[2..].iter();
+            while let Some(arg) = rest.next() {
+                if arg == "--field" {
+                    field = rest.next().cloned();
+                    if field.is_none() {
+                        println!("Missing value for --field");
+                        return Command::Invalid;
+                    }
+                } else if let Some(value) = arg.strip_prefix("--field=") {
+                    field = Some(value.to_string());
+                } else if name.is_none() {
+                    name = Some(arg.clone());
+                }
+            }
+            match name {
+                Some(name) => Command::Get { name, field },
+                None => {
+                    println!("Not enough arguments for get command");
+                    println!("Usage: rsp get <name> [--field <field>]");
+                    Command::Invalid
+                }
+            }
+        }
+        "history" => {
+            let usage = "Usage: rsp history <name> [--show] [--restore <n>]";
+            let (positional, options) = match split_options(&args[2..], &["--restore"]) {
+                Ok(split) => split,
+                Err(e) => {
+                    println!("{}", e);
+                    println!("{}", usage);
+                    return Command::Invalid;
+                }
+            };
+            let show = positional.iter().any(|arg| arg == "--show");
+            let Some(name) = positional.into_iter().find(|arg| arg != "--show") else {
+                println!("Not enough arguments for history command");
+                println!("{}", usage);
+                return Command::Invalid;
+            };
+            let restore = match options.last().map(|(_, n)| n.parse::<usize>()).transpose() {
+                Ok(Some(0)) | Err(_) => {
+                    println!("--restore takes the number shown by rsp history");
+                    return Command::Invalid;
+                }
+                Ok(restore) => restore,
+            };
+            Command::History { name, show, restore }
+        }
         "remove" => {
             if args.len() < 3 {
                 println!("Not enough arguments for remove command");
@@ -60,12 +416,113 @@ pub fn parse_args(args: Vec<String>) -> Command {
             Command::Remove { name: args[2].clone() }
         }
         "unlock" => {
-            let password = if args.len() > 2 { Some(args[2].clone()) } else { None };
-            Command::Unlock { password }
+            let usage = "Usage: rsp unlock [password] [--recovery]";
+            let mut password = None;
+            let mut recovery = false;
+            for arg in &args[2..] {
+                match arg.as_str() {
+                    "--recovery" => recovery = true,
+                    // Not echoed: a mistyped password may end up here
+                    other if other.starts_with("--") => {
+                        println!("Unknown option for unlock command");
+                        println!("{}", usage);
+                        return Command::Invalid;
+                    }
+                    _ if password.is_some() => {
+                        println!("Too many arguments for unlock command");
+                        println!("{}", usage);
+                        return Command::Invalid;
+                    }
+                    other => password = Some(other.to_string()),
+                }
+            }
+            Command::Unlock { password, recovery }
         }
         "lock" => Command::Lock,
+        "passwd" => Command::Passwd,
         "help" => Command::Help,
         "tui" => Command::Tui,
+        "vault" => {
+            if args.len() < 3 {
+                println!("Not enough arguments for vault command");
+                println!("Usage: rsp vault <list|verify [file]>");
+                return Command::Invalid;
+            }
+            match args[2].as_str() {
+                "list" => Command::VaultList,
+                "verify" => Command::VaultVerify { path: args.get(3).cloned() },
+                _ => {
+                    println!("Unknown vault command: {}", args[2]);
+                    println!("Usage: rsp vault <list|verify [file]>");
+                    Command::Invalid
+                }
+            }
+        }
+        "backup" => {
+            if args.len() < 3 {
+                println!("Not enough arguments for backup command");
+                println!("Usage: rsp backup [list|restore <number|file>]");
+                return Command::Invalid;
+            }
+            match args[2].as_str() {
+                "list" => Command::BackupList,
+                "restore" => {
+                    if args.len() < 4 {
+                        println!("Not enough arguments for backup restore command");
+                        println!("Usage: rsp backup restore <number|file>");
+                        return Command::Invalid;
+                    }
+                    Command::BackupRestore { backup: args[3].clone() }
+                }
+                _ => {
+                    println!("Unknown backup command: {}", args[2]);
+                    println!("Usage: rsp backup [list|restore <number|file>]");
+                    Command::Invalid
+                }
+            }
+        }
+        "key" => {
+            if args.len() < 3 {
+                println!("Not enough arguments for key command");
+                println!("Usage: rsp key [list|add <password|keyfile <path>|recovery>|remove <id>]");
+                return Command::Invalid;
+            }
+            match args[2].as_str() {
+                "list" => Command::KeyList,
+                "add" => match args.get(3).map(String::as_str) {
+                    Some("password") => Command::KeyAdd { kind: KeySlotKind::Password, keyfile: None },
+                    Some("recovery") => Command::KeyAdd { kind: KeySlotKind::RecoveryCode, keyfile: None },
+                    Some("keyfile") if args.len() > 4 => Command::KeyAdd {
+                        kind: KeySlotKind::KeyFile,
+                        keyfile: Some(args[4].clone()),
+                    },
+                    _ => {
+                        println!("Usage: rsp key add <password|keyfile <path>|recovery>");
+                        Command::Invalid
+                    }
+                },
+                "remove" => {
+                    if args.len() < 4 {
+                        println!("Not enough arguments for key remove command");
+                        println!("Usage: rsp key remove <id>");
+                        return Command::Invalid;
+                    }
+                    Command::KeyRemove { id: args[3].clone() }
+                }
+                _ => {
+                    println!("Unknown key command: {}", args[2]);
+                    println!("Usage: rsp key [list|add <password|keyfile <path>|recovery>|remove <id>]");
+                    Command::Invalid
+                }
+            }
+        }
+        "keyfile" => match args.get(2).map(String::as_str) {
+            Some("generate") => Command::KeyFileGenerate { path: args.get(3).cloned() },
+            _ => {
+                println!("Usage: rsp keyfile generate [path]");
+                Command::Invalid
+            }
+        },
         "daemon" => {
             if args.len() < 3 {
                 println!("Not enough arguments for daemon command");
@@ -93,13 +550,34 @@ pub fn parse_args(args: Vec<String>) -> Command {
 
 pub fn handle_command(command: Command) -> io::Result<()> {
     match command {
-        Command::Add { name, password } => execute_add(name, password),
-        Command::List => execute_list(),
+        Command::Add { kind, name, username, password, folder, tags, generate } => {
+            execute_add(kind, name, username, password, folder, tags, generate)
+        }
+        Command::Generate { options } => execute_generate(options),
+        Command::List { folder, tag, sort, older_than } => {
+            execute_list(folder, tag, sort, older_than)
+        }
+        Command::Get { name, field } => execute_get(name, field),
+        Command::History { name, show, restore } => execute_history(name, show, restore),
+        Command::TrashList => execute_trash_list(),
+        Command::TrashRestore { entry } => execute_trash_restore(entry),
+        Command::TrashEmpty => execute_trash_empty(),
         Command::Remove { name } => execute_remove(name),
-        Command::Unlock { password } => execute_unlock(password),
+        Command::Unlock { password, recovery } => execute_unlock(password, recovery),
         Command::Lock => execute_lock(),
+        Command::Passwd => execute_passwd(),
         Command::Help => execute_help(),
         Command::Tui => Ok(()), // This will be handled in main.rs
+        Command::VaultList => execute_vault_list(),
+        Command::VaultVerify { path } => execute_vault_verify(path),
+        Command::BackupList => execute_backup_list(),
+        Command::BackupRestore { backup } => execute_backup_restore(backup),
+        Command::KeyList => execute_key_list(),
+        Command::KeyAdd { kind, keyfile } => execute_key_add(kind, keyfile),
+        Command::KeyRemove { id } => execute_key_remove(id),
+        Command::KeyFileGenerate { path } => execute_keyfile_generate(path),
+        Command::FolderList => execute_folder_list(),
+        Command::FolderRename { from, to } => execute_folder_rename(from, to),
         Command::StartDaemon => {
             if DaemonClient::is_running() {
                 println!("Daemon is already running");
@@ -159,12 +637,12 @@ pub fn handle_command(command: Command) -> io::Result<()> {
                     Ok(state) => {
                         println!("Daemon is running");
                         println!("State: {}", if state.unlocked { "Unlocked" } else { "Locked" });
+                        if let Some(vault) = &state.vault {
+                            println!("Vault: {}", vault);
+                        }
 
                         // Get log file contents
-                        let log_path = dirs
-                            ::data_dir()
-                            .unwrap_or_else(|| PathBuf::from("/tmp"))
-                            .join("rustpass/daemon.log");
+                        let log_path = get_log_path(DAEMON_LOG_FILE_NAME);
 
                         if let Ok(log_content) = fs::read_to_string(log_path) {
                             let recent_logs: Vec<_> = log_content.lines().rev().take(10).collect();
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::models::structs::CipherKind;
+
+/// Where `rsp unlock` keeps the vault key for later commands.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionStore {
+    /// Encrypted in the state directory, opened with the `RSP_SESSION` token.
+    #[default]
+    File,
+    /// Kernel user keyring, shared by all processes of the user.
+    UserKeyring,
+    /// Kernel session keyring, scoped to the login session.
+    SessionKeyring,
+}
+
+/// User settings read from `$XDG_CONFIG_HOME/rustpass/config.json`.
+/// Missing keys fall back to their defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Number of encrypted backups kept next to each vault (0 disables backups).
+    pub backup_count: usize,
+    /// Cipher new vaults are created with; existing vaults keep the one in their header.
+    pub vault_cipher: CipherKind,
+    /// Seconds an `rsp unlock` session stays valid (0 keeps it until `rsp lock`).
+    pub session_timeout_secs: u64,
+    pub session_store: SessionStore,
+    /// Refuse to run if core dumps, ptrace or memory locking cannot be restricted.
+    pub require_hardening: bool,
+    /// Minimum level written to the log (`off`, `error`, `warn`, `info`, `debug`, `trace`).
+    pub log_level: String,
+    /// Size after which a log file is rotated (0 never rotates).
+    pub log_max_bytes: u64,
+    /// Number of rotated log files kept.
+    pub log_keep_files: usize,
+    /// Days after which a password is flagged for rotation (0 never flags it).
+    pub password_max_age_days: u64,
+    /// Previous passwords kept per entry (0 keeps none).
+    pub password_history_limit: usize,
+    /// Days deleted entries stay in the trash before a save purges them (0 keeps them).
+    pub trash_retention_days: u64,
+    /// Lowest strength score (0 very weak to 4 very strong) a login password may have.
+    pub password_min_strength: u8,
+    /// Save weaker passwords anyway, with a warning instead of an error.
+    pub password_strength_warn_only: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            backup_count: 10,
+            vault_cipher: CipherKind::default(),
+            session_timeout_secs: 3600,
+            session_store: SessionStore::default(),
+            require_hardening: false,
+            log_level: "info".to_string(),
+            log_max_bytes: 1024 * 1024,
+            log_keep_files: 3,
+            password_max_age_days: 90,
+            password_history_limit: 10,
+            trash_retention_days: 30,
+            password_min_strength: 2,
+            password_strength_warn_only: false,
+        }
+    }
+}
+
+// Get config file path
+pub fn get_config_file_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join("rustpass")
+        .join("config.json")
+}
+
+// load the settings, falling back to defaults if the file is missing or invalid:
+pub fn load_settings() -> Settings {
+    let path = get_config_file_path();
+    if !path.exists() {
+        return Settings::default();
+    }
+
+    match fs::read_to_string(&path) {
+        Ok(contents) => match serde_json::from_str(&contents) {
+            Ok(settings) => settings,
+            Err(e) => {
+                log::warn!("Invalid config file {:?}: {}. Using defaults.", path, e);
+                Settings::default()
+            }
+        },
+        Err(e) => {
+            log::warn!("Failed to read config file {:?}: {}. Using defaults.", path, e);
+            Settings::default()
+        }
+    }
+}
+
+// Settings are read once per process
+lazy_static::lazy_static! {
+    pub static ref SETTINGS: Settings = load_settings();
+}
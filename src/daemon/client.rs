@@ -5,6 +5,9 @@ use std::fs;
 use std::path::PathBuf;
 use dirs;
 
+use crate::auth::keyfile::active_key_file;
+use crate::data::vault::active_vault_name;
+
 use super::ipc::{ DaemonCommand, DaemonResponse, DaemonState, get_socket_path, get_state_path };
 
 pub struct DaemonClient;
@@ -52,7 +55,11 @@ impl DaemonClient {
 
         to_writer(
             &mut connection,
-            &(DaemonCommand::Unlock { password: password.to_string() })
+            &(DaemonCommand::Unlock {
+                password: password.into(),
+                vault: active_vault_name(),
+                keyfile: active_key_file(),
+            })
         ).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
 
         let response: DaemonResponse = from_reader(&mut connection).map_err(|e|
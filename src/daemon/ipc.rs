@@ -1,9 +1,13 @@
 use std::fs::{ self, File };
-use std::io::{ self, Read, Write };
+use std::io::{ self, Read };
 use std::path::PathBuf;
 use serde::{ Deserialize, Serialize };
 use interprocess::local_socket::LocalSocketStream;
 
+use crate::models::secret::SecretString;
+use crate::models::structs::VaultHeader;
+use crate::utils::atomic_file::write_atomic;
+
 // Directory for IPC files
 pub fn get_ipc_dir() -> PathBuf {
     // Use XDG_RUNTIME_DIR or equivalent on different platforms
@@ -39,32 +43,28 @@ pub fn get_state_path() -> PathBuf {
     get_ipc_dir().join("state.json")
 }
 
-// Don't serialize the encryption key directly, store it as Base64 string
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DaemonState {
     pub unlocked: bool,
-    // Use Vec<u8> instead of array for easier serialization
-    pub encryption_key: Option<Vec<u8>>,
-    pub salt: Option<Vec<u8>>,
+    pub header: Option<VaultHeader>,
+    // Name of the vault the key belongs to
+    pub vault: Option<String>,
 }
 
 impl Default for DaemonState {
     fn default() -> Self {
         Self {
             unlocked: false,
-            encryption_key: None,
-            salt: None,
+            header: None,
+            vault: None,
         }
     }
 }
 
-// Save daemon state to file
+// Save daemon state to file; it is replaced atomically and created owner-only
 pub fn save_daemon_state(state: &DaemonState) -> io::Result<()> {
-    let path = get_state_path();
     let json = serde_json::to_string(state)?;
-    let mut file = File::create(path)?;
-    file.write_all(json.as_bytes())?;
-    Ok(())
+    write_atomic(&get_state_path(), json.as_bytes())
 }
 
 // Load daemon state from file
@@ -89,7 +89,11 @@ pub fn load_daemon_state() -> io::Result<DaemonState> {
 #[derive(Serialize, Deserialize, Debug)]
 pub enum DaemonCommand {
     Unlock {
-        password: String,
+        password: SecretString,
+        vault: String,
+        /// Contents of the key file used as second factor, if any.
+        #[serde(default)]
+        keyfile: Option<Vec<u8>>,
     },
     Lock,
     GetState,
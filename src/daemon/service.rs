@@ -1,15 +1,15 @@
 use crate::daemon::ipc::{load_daemon_state, save_daemon_state, DaemonState};
+use crate::models::secret::SecretString;
 use crate::data::data::load_passwords;
-use crate::PASSWORD_FILE_PATH;
+use crate::data::vault::{get_data_dir, get_vault_path_for, validate_vault_name};
+use crate::utils::logger::{get_log_path, open_log_file, set_log_file, DAEMON_LOG_FILE_NAME};
 
 use daemonize;
-use env_logger;
 use interprocess::local_socket::{LocalSocketListener, LocalSocketStream};
 use log::{error, info};
 use serde_json::{from_reader, to_writer};
 use std::fs;
 use std::io;
-use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
@@ -24,6 +24,8 @@ pub struct DaemonService {
 impl DaemonService {
     pub fn new() -> Self {
         let state = match load_daemon_state() {
+            // A restarted daemon always starts locked
+            Ok(state) if state.unlocked => DaemonState::default(),
             Ok(state) => state,
             Err(e) => {
                 error!("Failed to load daemon state: {}", e);
@@ -97,8 +99,8 @@ impl DaemonService {
                 let state = state.lock().unwrap().clone();
                 DaemonResponse::StateInfo(state)
             }
-            Ok(DaemonCommand::Unlock { password }) => {
-                match Self::perform_unlock(password, state.clone()) {
+            Ok(DaemonCommand::Unlock { password, vault, keyfile }) => {
+                match Self::perform_unlock(password, vault, keyfile, state.clone()) {
                     Ok(_) => DaemonResponse::Success,
                     Err(e) => DaemonResponse::Error(e.to_string()),
                 }
@@ -106,8 +108,8 @@ impl DaemonService {
             Ok(DaemonCommand::Lock) => {
                 let mut state = state.lock().unwrap();
                 state.unlocked = false;
-                state.encryption_key = None;
-                state.salt = None;
+                state.header = None;
+                state.vault = None;
                 let _ = save_daemon_state(&state);
                 DaemonResponse::Success
             }
@@ -124,13 +126,22 @@ impl DaemonService {
         let _ = to_writer(&mut conn, &response);
     }
 
-    fn perform_unlock(password: String, state: Arc<Mutex<DaemonState>>) -> io::Result<()> {
-        match load_passwords(PASSWORD_FILE_PATH, &password) {
-            Ok((_, key, salt)) => {
+    fn perform_unlock(
+        password: SecretString,
+        vault: String,
+        keyfile: Option<Vec<u8>>,
+        state: Arc<Mutex<DaemonState>>,
+    ) -> io::Result<()> {
+        validate_vault_name(&vault).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        // Only the password is checked; clients never get a key from the daemon, so it
+        // doesn't keep one
+        match load_passwords(&get_vault_path_for(&vault), &password, keyfile.as_deref()) {
+            Ok((_, _, header)) => {
                 let mut state = state.lock().unwrap();
                 state.unlocked = true;
-                state.encryption_key = Some(key.to_vec());
-                state.salt = Some(salt);
+                state.header = Some(header);
+                state.vault = Some(vault);
                 save_daemon_state(&state)?;
                 Ok(())
             }
@@ -143,40 +154,27 @@ impl DaemonService {
     }
 
     pub fn start_daemon() -> io::Result<()> {
-        use std::fs::OpenOptions;
-
-        // Create system directories if they don't exist
-        let log_dir = dirs::data_dir()
-            .unwrap_or_else(|| PathBuf::from("/tmp"))
-            .join("rustpass");
-        fs::create_dir_all(&log_dir)?;
+        let data_dir = get_data_dir();
 
-        // Setup log file
-        let log_file = log_dir.join("daemon.log");
-        let stdout = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&log_file)?;
+        // Anything the daemon prints (e.g. a panic) ends up in its owner-only log
+        let stdout = open_log_file(&get_log_path(DAEMON_LOG_FILE_NAME))?;
 
         // Setup PID file
-        let pid_file = log_dir.join("daemon.pid");
+        let pid_file = data_dir.join("daemon.pid");
 
         // Configure the daemon
         let daemonize = daemonize::Daemonize::new()
             .pid_file(pid_file)
             .chown_pid_file(true)
-            .working_directory(log_dir)
+            .working_directory(data_dir)
             .stdout(stdout.try_clone()?)
             .stderr(stdout);
 
         // Start the daemon
         match daemonize.start() {
             Ok(_) => {
-                // Initialize logging
-                env_logger::Builder::from_env(env_logger::Env::default())
-                    .filter_level(log::LevelFilter::Info)
-                    .format_timestamp_secs()
-                    .init();
+                // The logger was set up by the parent; switch it to the daemon log
+                set_log_file(DAEMON_LOG_FILE_NAME);
 
                 info!("Daemon started successfully");
 
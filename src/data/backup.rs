@@ -0,0 +1,232 @@
+use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use crate::auth::keyslots::{unlock_header, UnlockSecret};
+use crate::data::data::{decrypt_entries, encrypt_entries, parse_encrypted_file};
+use crate::data::lock::VaultLock;
+use crate::data::revision::{authenticated_revision, last_seen_revision, note_revision};
+use crate::models::structs::{VaultHeader, VAULT_FORMAT_VERSION};
+use crate::models::types::PasswordDataResult;
+use crate::utils::atomic_file::write_atomic;
+
+// Timestamp format used in backup file names (sorts chronologically)
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S%.6f";
+
+/// An encrypted copy of a vault taken before it was overwritten.
+pub struct BackupInfo {
+    pub path: PathBuf,
+    pub file_name: String,
+    pub created: Option<NaiveDateTime>,
+    pub size: u64,
+}
+
+impl BackupInfo {
+    // human readable creation time in local time:
+    pub fn created_display(&self) -> String {
+        match self.created {
+            Some(created) => Local
+                .from_utc_datetime(&created)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+            None => "unknown".into(),
+        }
+    }
+}
+
+// Backups of `vaults/<name>.json` live in `vaults/backups/<name>/`
+pub fn get_backup_dir(vault_path: &Path) -> PathBuf {
+    let parent = vault_path.parent().unwrap_or_else(|| Path::new("."));
+    let stem = vault_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("vault");
+    parent.join("backups").join(stem)
+}
+
+// copy the current vault into the backup ring, then drop the oldest backups:
+pub fn create_backup(vault_path: &Path, keep: usize) -> io::Result<Option<PathBuf>> {
+    if keep == 0 || !vault_path.exists() {
+        return Ok(None);
+    }
+
+    let backup_dir = get_backup_dir(vault_path);
+    fs::create_dir_all(&backup_dir)?;
+    fs::set_permissions(&backup_dir, fs::Permissions::from_mode(0o700))?;
+
+    let timestamp = Utc::now().naive_utc().format(BACKUP_TIMESTAMP_FORMAT);
+    let backup_path = backup_dir.join(format!("{}.json", timestamp));
+
+    let contents = fs::read(vault_path)?;
+    write_atomic(&backup_path, &contents)?;
+
+    rotate_backups(vault_path, keep)?;
+    Ok(Some(backup_path))
+}
+
+// delete all but the `keep` newest backups:
+pub fn rotate_backups(vault_path: &Path, keep: usize) -> io::Result<()> {
+    let backups = list_backups(vault_path)?;
+    for backup in backups.into_iter().skip(keep) {
+        fs::remove_file(&backup.path)?;
+    }
+    Ok(())
+}
+
+// list the backups of a vault, newest first:
+pub fn list_backups(vault_path: &Path) -> io::Result<Vec<BackupInfo>> {
+    let backup_dir = get_backup_dir(vault_path);
+    if !backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups: Vec<BackupInfo> = fs::read_dir(&backup_dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                return None;
+            }
+            let file_name = path.file_name()?.to_str()?.to_string();
+            let stem = path.file_stem()?.to_str()?;
+            let created = NaiveDateTime::parse_from_str(stem, BACKUP_TIMESTAMP_FORMAT).ok();
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            Some(BackupInfo {
+                path,
+                file_name,
+                created,
+                size,
+            })
+        })
+        .collect();
+
+    backups.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+    Ok(backups)
+}
+
+// find a backup by its position in `list_backups` (1-based) or its file name:
+pub fn find_backup(vault_path: &Path, selector: &str) -> Result<BackupInfo, String> {
+    let backups = list_backups(vault_path).map_err(|e| format!("Failed to list backups: {}", e))?;
+
+    if let Ok(index) = selector.parse::<usize>() {
+        if index >= 1 && index <= backups.len() {
+            return Ok(backups.into_iter().nth(index - 1).unwrap());
+        }
+    }
+
+    backups
+        .into_iter()
+        .find(|b| b.file_name == selector || b.file_name.trim_end_matches(".json") == selector)
+        .ok_or_else(|| format!("Backup '{}' not found", selector))
+}
+
+// replace the vault with a backup, keeping the current vault as a new backup. The
+// restored entries are saved as a new revision, above the current vault and every
+// revision seen, so the rollback protection is never lowered:
+pub fn restore_backup(
+    vault_path: &Path,
+    backup: &BackupInfo,
+    secret: &UnlockSecret,
+    keep: usize,
+) -> PasswordDataResult {
+    let contents = fs::read_to_string(&backup.path)
+        .map_err(|e| format!("Failed to read backup: {}", e))?;
+
+    // Refuse to restore something that is not a vault file
+    let restored = parse_encrypted_file(&contents)?;
+    let key = unlock_header(&restored.header, secret)
+        .map_err(|_| "The password does not open this backup".to_string())?;
+    let passwords = decrypt_entries(&restored, &key)?;
+
+    let _lock = VaultLock::acquire(vault_path)
+        .map_err(|e| format!("Failed to lock vault: {}", e))?;
+
+    // Move generation and revision past the current vault so stale in-memory copies
+    // can't overwrite it and the revision seen only moves forward
+    let mut generation = restored.header.generation;
+    let mut revision = authenticated_revision(&restored.header);
+    if let Ok(current) = fs::read_to_string(vault_path) {
+        if let Ok(current) = parse_encrypted_file(&current) {
+            generation = generation.max(current.header.generation);
+            revision = revision.max(authenticated_revision(&current.header));
+        }
+    }
+    revision = revision.max(last_seen_revision(vault_path).unwrap_or(0));
+
+    let header = VaultHeader {
+        version: VAULT_FORMAT_VERSION,
+        generation: generation + 1,
+        revision: revision + 1,
+        ..restored.header
+    };
+    let encrypted = encrypt_entries(&passwords, &key, &header)?;
+    let contents = serde_json::to_string(&encrypted)
+        .map_err(|e| format!("Serialization failed: {}", e))?;
+
+    create_backup(vault_path, keep.max(1))
+        .map_err(|e| format!("Failed to back up current vault: {}", e))?;
+
+    write_atomic(vault_path, contents.as_bytes())
+        .map_err(|e| format!("Failed to restore backup: {}", e))?;
+
+    note_revision(vault_path, &header);
+    Ok((passwords, key, header))
+}
+
+// re-encrypt the backups `old_key` opens with `key`, under the key slots and cipher of
+// `header`, so they open with the same secrets as the vault. Each backup keeps its own
+// revision. Returns how many were moved:
+pub fn rekey_backups(
+    vault_path: &Path,
+    old_key: &[u8; 32],
+    key: &[u8; 32],
+    header: &VaultHeader,
+) -> Result<usize, String> {
+    let backups = list_backups(vault_path).map_err(|e| format!("Failed to list backups: {}", e))?;
+
+    let mut moved = 0;
+    for backup in backups {
+        let Ok(contents) = fs::read_to_string(&backup.path) else {
+            continue;
+        };
+        let Ok(encrypted) = parse_encrypted_file(&contents) else {
+            continue;
+        };
+        // Backups from before an earlier key change are left to `backups_with_other_keys`
+        let Ok(passwords) = decrypt_entries(&encrypted, old_key) else {
+            continue;
+        };
+
+        let backup_header = VaultHeader {
+            version: VAULT_FORMAT_VERSION,
+            generation: encrypted.header.generation,
+            revision: encrypted.header.revision,
+            ..header.clone()
+        };
+        let rekeyed = encrypt_entries(&passwords, key, &backup_header)?;
+        let contents = serde_json::to_string(&rekeyed)
+            .map_err(|e| format!("Serialization failed: {}", e))?;
+        write_atomic(&backup.path, contents.as_bytes())
+            .map_err(|e| format!("Failed to write backup {}: {}", backup.file_name, e))?;
+        moved += 1;
+    }
+    Ok(moved)
+}
+
+// list the backups the vault's data key does not open; they are still encrypted with
+// an earlier key and open with whatever secrets it had:
+pub fn backups_with_other_keys(vault_path: &Path, key: &[u8; 32]) -> Result<Vec<BackupInfo>, String> {
+    let backups = list_backups(vault_path).map_err(|e| format!("Failed to list backups: {}", e))?;
+    Ok(backups
+        .into_iter()
+        .filter(|backup| {
+            let opens = fs::read_to_string(&backup.path)
+                .ok()
+                .and_then(|contents| parse_encrypted_file(&contents).ok())
+                .is_some_and(|encrypted| decrypt_entries(&encrypted, key).is_ok());
+            !opens
+        })
+        .collect())
+}
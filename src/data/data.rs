@@ -1,111 +1,543 @@
 use base64::{engine::general_purpose::STANDARD, Engine as _};
 use serde_json;
+use std::collections::HashMap;
 use std::fs::{set_permissions, File};
-use std::io::{self, Read, Write};
+use std::io::{self, Read};
 use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
 
-use crate::auth::handler::derive_key;
-use crate::models::structs::{EncryptedFile, PasswordEntry};
-use crate::models::types::{
-    DecryptedDataResult, EncryptedDataResult, OperationResult, PasswordDataResult,
+use crate::auth::keyslots::{
+    create_key_slot, generate_data_key, open_key_slot, unlock_header, unlock_header_slot,
+    UnlockSecret,
 };
-use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit};
+use crate::config::settings::SETTINGS;
+use crate::data::backup::{create_backup, rekey_backups};
+use crate::data::trash::purge_expired;
+use crate::encryption::cipher::{decrypt_with, encrypt_with};
+use crate::data::lock::VaultLock;
+use crate::data::revision::{check_revision, note_revision};
+use crate::models::structs::{
+    CipherKind, EncryptedFile, KdfVersion, KeySlot, PasswordEntry, Timestamps,
+    VaultHeader, AUTHENTICATED_HEADER_VERSION, VAULT_FORMAT_VERSION,
+};
+use crate::models::types::{EncryptionKey, OperationResult, PasswordDataResult};
+use crate::utils::atomic_file::write_atomic;
 
-// Encrypt data
-fn encrypt_data(data: &[u8], key: &[u8; 32]) -> EncryptedDataResult {
-    let cipher = Aes256Gcm::new(key.into());
-    let mut nonce = [0u8; 12];
-    for byte in &mut nonce {
-        *byte = rand::random();
+// Namespace of the ids given to entries that predate the username field
+const LEGACY_ENTRY_NAMESPACE: uuid::Uuid = uuid::Uuid::from_u128(0x5f0c_2b1e_8d4a_4c7e_9a63_1e2f_7b90_d845);
+
+// Parse the vault file, rejecting format versions this build does not understand
+pub fn parse_encrypted_file(contents: &str) -> Result<EncryptedFile, String> {
+    let raw: serde_json::Value =
+        serde_json::from_str(contents).map_err(|e| format!("Invalid file format: {}", e))?;
+
+    if let Some(version) = raw.get("version") {
+        let version = version
+            .as_u64()
+            .ok_or_else(|| format!("Invalid vault format version: {}", version))?;
+        if version > VAULT_FORMAT_VERSION as u64 {
+            return Err(format!(
+                "Unsupported vault format version {} (this build supports up to version {}). Please upgrade rustpass.",
+                version, VAULT_FORMAT_VERSION
+            ));
+        }
     }
-    let ciphertext = cipher
-        .encrypt(&nonce.into(), data)
-        .map_err(|e| format!("Encryption failed: {}", e))?;
-    Ok((nonce.to_vec(), ciphertext))
+
+    serde_json::from_value(raw).map_err(|e| format!("Invalid file format: {}", e))
 }
 
-// Decrypt data
-fn decrypt_data(ciphertext: &[u8], key: &[u8; 32], nonce: &[u8]) -> DecryptedDataResult {
-    let cipher = Aes256Gcm::new(key.into());
-    cipher
-        .decrypt(nonce.into(), ciphertext)
-        .map_err(|e| format!("Decryption failed: {}", e))
+// Unlock the vault with the master password and, if the vault requires one, a key file
+pub fn load_passwords(
+    file_path: &Path,
+    password: &str,
+    key_file: Option<&[u8]>,
+) -> PasswordDataResult {
+    load_passwords_with_secret(file_path, &UnlockSecret::Password(password, key_file))
 }
 
-pub fn load_passwords(file_path: &str, password: &str) -> PasswordDataResult {
-    let mut file = File::open(file_path).map_err(|e| format!("Failed to open file: {}", e))?;
-    let mut contents = String::new();
+// Unlock the vault with any secret that opens one of its key slots
+pub fn load_passwords_with_secret(file_path: &Path, secret: &UnlockSecret) -> PasswordDataResult {
+    let encrypted = read_encrypted_file(file_path)?;
 
-    file.read_to_string(&mut contents)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-    //log::debug!("File contents: {}", contents);
+    if encrypted.header.is_legacy() {
+        if let UnlockSecret::Password(password, None) = secret {
+            return migrate_legacy_vault(file_path, password);
+        }
+    }
 
-    let encrypted: EncryptedFile =
-        serde_json::from_str(&contents).map_err(|e| format!("Invalid file format: {}", e))?;
-    //log::debug!("Encrypted salt: {}", encrypted.salt);
+    let (key, slot) = unlock_header_slot(&encrypted.header, secret)?;
+    let passwords = decrypt_entries(&encrypted, &key)?;
+    note_revision(file_path, &encrypted.header);
 
-    let salt = STANDARD
-        .decode(&encrypted.salt)
-        .map_err(|e| format!("Invalid salt: {}", e))?;
-    //log::debug!("Decoded salt: {:?}", salt);
+    // Passwords opened through the old `fibbil_hash` KDF are re-wrapped with the current one
+    if let (UnlockSecret::Password(..), Some(slot)) = (secret, slot) {
+        let slot = &encrypted.header.key_slots[slot];
+        if slot.kdf.version == KdfVersion::V1 {
+            match upgrade_password_slot(file_path, &key, secret, &slot.id) {
+                Ok((passwords, header)) => return Ok((passwords, key, header)),
+                Err(e) => log::warn!("Failed to upgrade key derivation of {:?}: {}", file_path, e),
+            }
+        }
+    }
 
-    let nonce = STANDARD
-        .decode(&encrypted.nonce)
-        .map_err(|e| format!("Invalid nonce: {}", e))?;
+    Ok((passwords, key, encrypted.header))
+}
 
-    let encrypted_data = STANDARD
-        .decode(&encrypted.encrypted_data)
-        .map_err(|e| format!("Invalid encrypted data: {}", e))?;
+// Replace a password slot derived with KDF v1 by one using the current KDF, keeping its
+// id, label and creation time
+fn upgrade_password_slot(
+    file_path: &Path,
+    key: &EncryptionKey,
+    secret: &UnlockSecret,
+    slot_id: &str,
+) -> Result<(Vec<PasswordEntry>, VaultHeader), String> {
+    let _lock = VaultLock::acquire(file_path)
+        .map_err(|e| format!("Failed to lock vault: {}", e))?;
 
-    let key = derive_key(password, &salt)?;
-    let decrypted = decrypt_data(&encrypted_data, &key, &nonce)?;
-    let passwords: Vec<PasswordEntry> = serde_json::from_slice(&decrypted)
-        .map_err(|e| format!("Failed to parse passwords: {}", e))?;
+    let encrypted = read_encrypted_file(file_path)?;
+    let passwords = decrypt_entries(&encrypted, key)?;
+    let mut header = encrypted.header;
+
+    // Another process may have upgraded or removed it while we waited for the lock
+    let slot = match header.key_slots.iter_mut().find(|slot| slot.id == slot_id) {
+        Some(slot) if slot.kdf.version == KdfVersion::V1 => slot,
+        _ => return Ok((passwords, header)),
+    };
+    let upgraded = create_key_slot(key, secret, &slot.label)?;
+    *slot = KeySlot {
+        id: slot.id.clone(),
+        created: slot.created.clone(),
+        ..upgraded
+    };
 
-    Ok((passwords, key, salt))
+    write_vault(file_path, &passwords, key, &mut header)?;
+    log::info!("Upgraded key slot {} of {:?} to KDF v2", slot_id, file_path);
+    Ok((passwords, header))
+}
+
+// Load the vault with an already unwrapped data key
+pub fn load_vault_with_key(file_path: &Path, key: &EncryptionKey) -> PasswordDataResult {
+    let encrypted = read_encrypted_file(file_path)?;
+    let passwords = decrypt_entries(&encrypted, key)?;
+    note_revision(file_path, &encrypted.header);
+    Ok((passwords, key.clone(), encrypted.header))
+}
+
+// Read the vault header without unlocking anything
+pub fn read_vault_header(file_path: &Path) -> Result<VaultHeader, String> {
+    Ok(read_encrypted_file(file_path)?.header)
+}
+
+// Move a vault without key slots to envelope encryption: the old password-derived
+// key becomes the data key and is wrapped in a new master password slot
+fn migrate_legacy_vault(file_path: &Path, password: &str) -> PasswordDataResult {
+    let _lock = VaultLock::acquire(file_path)
+        .map_err(|e| format!("Failed to lock vault: {}", e))?;
+
+    let encrypted = read_encrypted_file(file_path)?;
+    let key = unlock_header(&encrypted.header, &UnlockSecret::Password(password, None))?;
+    let passwords = decrypt_entries(&encrypted, &key)?;
+
+    // Another process may have migrated it while we waited for the lock
+    if !encrypted.header.is_legacy() {
+        return Ok((passwords, key, encrypted.header));
+    }
+
+    let slot = create_key_slot(&key, &UnlockSecret::Password(password, None), "master password")?;
+    let mut header = VaultHeader {
+        generation: encrypted.header.generation,
+        revision: encrypted.header.revision,
+        ..VaultHeader::new(vec![slot])
+    };
+    write_vault(file_path, &passwords, &key, &mut header)?;
+    log::info!("Migrated vault {:?} to key slots", file_path);
+
+    Ok((passwords, key, header))
 }
 
 pub fn save_passwords(
-    file_path: &str,
+    file_path: &Path,
     passwords: &[PasswordEntry],
     key: &[u8; 32],
-    salt: &[u8],
+    header: &mut VaultHeader,
 ) -> OperationResult {
-    let data = serde_json::to_vec(passwords).map_err(|e| format!("Serialization failed: {}", e))?;
-    let (nonce, encrypted_data) = encrypt_data(&data, key)?;
+    let _lock = VaultLock::acquire(file_path)
+        .map_err(|e| format!("Failed to lock vault: {}", e))?;
 
-    let encrypted_file = EncryptedFile {
-        salt: STANDARD.encode(salt),
-        nonce: STANDARD.encode(&nonce),
-        encrypted_data: STANDARD.encode(&encrypted_data),
+    // Refuse to overwrite changes another process saved after we loaded the vault
+    if let Some(disk_generation) = read_disk_generation(file_path)? {
+        if disk_generation != header.generation {
+            return Err(
+                "Vault was modified by another process since it was loaded. Reload and try again."
+                    .into(),
+            );
+        }
+    }
+
+    write_vault(file_path, passwords, key, header)
+}
+
+// Re-read the vault under the lock, apply `change` to the fresh entries and save them,
+// so concurrent edits from the CLI, TUI and daemon are merged instead of clobbered
+pub fn update_passwords<T, F>(
+    file_path: &Path,
+    key: &[u8; 32],
+    header: &mut VaultHeader,
+    change: F,
+) -> Result<(Vec<PasswordEntry>, T), String>
+where
+    F: FnOnce(&mut Vec<PasswordEntry>) -> Result<T, String>,
+{
+    update_vault(file_path, key, header, change, true)
+}
+
+// Stamp the entry with `id` as used just now. Nothing but the access time changes, so
+// no backup is made for it; otherwise copying passwords would push real edits out of
+// the backups.
+pub fn record_use(
+    file_path: &Path,
+    key: &[u8; 32],
+    header: &mut VaultHeader,
+    id: &str,
+) -> Result<Vec<PasswordEntry>, String> {
+    let (passwords, ()) = update_vault(file_path, key, header, |passwords| {
+        let entry = passwords
+            .iter_mut()
+            .find(|entry| entry.id == id)
+            .ok_or("Entry was removed by another process")?;
+        entry.times.last_used = Timestamps::now();
+        Ok(())
+    }, false)?;
+    Ok(passwords)
+}
+
+fn update_vault<T, F>(
+    file_path: &Path,
+    key: &[u8; 32],
+    header: &mut VaultHeader,
+    change: F,
+    backup: bool,
+) -> Result<(Vec<PasswordEntry>, T), String>
+where
+    F: FnOnce(&mut Vec<PasswordEntry>) -> Result<T, String>,
+{
+    let _lock = VaultLock::acquire(file_path)
+        .map_err(|e| format!("Failed to lock vault: {}", e))?;
+
+    let encrypted = read_encrypted_file(file_path)?;
+    let mut disk_header = encrypted.header.clone();
+    let mut passwords = decrypt_entries(&encrypted, key)
+        .map_err(|_| "Vault was re-keyed by another process. Please unlock again.".to_string())?;
+    let result = change(&mut passwords)?;
+    let purged = purge_expired(&mut passwords, SETTINGS.trash_retention_days);
+    if purged > 0 {
+        log::info!("Purged {} entries past the trash retention period", purged);
+    }
+
+    write_vault_file(file_path, &passwords, key, &mut disk_header, backup)?;
+    *header = disk_header;
+    Ok((passwords, result))
+}
+
+// Encrypt and write the vault, bumping the header generation and revision. Caller must
+// hold the lock.
+fn write_vault(
+    file_path: &Path,
+    passwords: &[PasswordEntry],
+    key: &[u8; 32],
+    header: &mut VaultHeader,
+) -> OperationResult {
+    write_vault_file(file_path, passwords, key, header, true)
+}
+
+fn write_vault_file(
+    file_path: &Path,
+    passwords: &[PasswordEntry],
+    key: &[u8; 32],
+    header: &mut VaultHeader,
+    backup: bool,
+) -> OperationResult {
+    // Always write the current format; the KDF settings stay those the key was derived with
+    let new_header = VaultHeader {
+        version: VAULT_FORMAT_VERSION,
+        generation: header.generation + 1,
+        revision: header.revision + 1,
+        ..header.clone()
     };
 
-    let mut file = File::create(file_path).map_err(|e| format!("Failed to create file: {}", e))?;
-    file.write_all(serde_json::to_string(&encrypted_file).unwrap().as_bytes())
+    let encrypted_file = encrypt_entries(passwords, key, &new_header)?;
+    let contents = serde_json::to_string(&encrypted_file)
+        .map_err(|e| format!("Serialization failed: {}", e))?;
+
+    // Keep the previous version around before replacing it
+    if backup {
+        if let Err(e) = create_backup(file_path, SETTINGS.backup_count) {
+            log::warn!("Failed to back up vault before saving: {}", e);
+        }
+    }
+
+    // Write to a temp file and rename it over the vault so a crash never truncates it
+    write_atomic(file_path, contents.as_bytes())
         .map_err(|e| format!("Failed to write file: {}", e))?;
 
     // Set file permissions to 600 (owner read/write only)
     let perms = std::fs::Permissions::from_mode(0o600);
     set_permissions(file_path, perms).map_err(|e| format!("Failed to set permissions: {}", e))?;
 
+    note_revision(file_path, &new_header);
+    *header = new_header;
     Ok(())
 }
 
-pub fn create_password_file(file_path: &str, password: &str) -> PasswordDataResult {
+// Encrypt the entries with the data key, binding them to `header`
+pub fn encrypt_entries(
+    passwords: &[PasswordEntry],
+    key: &[u8; 32],
+    header: &VaultHeader,
+) -> Result<EncryptedFile, String> {
+    let data = serde_json::to_vec(passwords).map_err(|e| format!("Serialization failed: {}", e))?;
+    let aad = header_associated_data(header)?;
+    let (nonce, encrypted_data) = encrypt_with(header.cipher, &data, &aad, key)?;
+
+    Ok(EncryptedFile {
+        header: header.clone(),
+        nonce: STANDARD.encode(&nonce),
+        encrypted_data: STANDARD.encode(&encrypted_data),
+    })
+}
+
+// Read and parse the vault file, refusing one older than the last revision seen
+fn read_encrypted_file(file_path: &Path) -> Result<EncryptedFile, String> {
+    let contents =
+        std::fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let encrypted = parse_encrypted_file(&contents)?;
+    check_revision(file_path, &encrypted.header)?;
+    Ok(encrypted)
+}
+
+// Bytes binding the header to the entries. `generation` is left out: it only detects
+// concurrent writes, and restoring a backup has to move it without the data key.
+pub fn header_associated_data(header: &VaultHeader) -> Result<Vec<u8>, String> {
+    if header.version < AUTHENTICATED_HEADER_VERSION {
+        return Ok(Vec::new());
+    }
+    let bound = VaultHeader {
+        generation: 0,
+        ..header.clone()
+    };
+    let mut aad = b"rustpass-vault-header\0".to_vec();
+    serde_json::to_writer(&mut aad, &bound).map_err(|e| format!("Serialization failed: {}", e))?;
+    Ok(aad)
+}
+
+// Decrypt the entries of a parsed vault file with an already derived key
+pub fn decrypt_entries(encrypted: &EncryptedFile, key: &[u8; 32]) -> Result<Vec<PasswordEntry>, String> {
+    let nonce = STANDARD
+        .decode(&encrypted.nonce)
+        .map_err(|e| format!("Invalid nonce: {}", e))?;
+
+    let encrypted_data = STANDARD
+        .decode(&encrypted.encrypted_data)
+        .map_err(|e| format!("Invalid encrypted data: {}", e))?;
+
+    let aad = header_associated_data(&encrypted.header)?;
+    let decrypted = decrypt_with(encrypted.header.cipher, &encrypted_data, &aad, key, &nonce)?;
+    let mut passwords: Vec<PasswordEntry> = serde_json::from_slice(&decrypted)
+        .map_err(|e| format!("Failed to parse passwords: {}", e))?;
+    migrate_entry_ids(&mut passwords);
+    Ok(passwords)
+}
+
+// Entries written before the username field kept the login in `id`. Move it to
+// `username` and give the entry a UUID derived from what it held, so every process
+// that reads the vault before it is saved again agrees on the new id.
+pub fn migrate_entry_ids(passwords: &mut [PasswordEntry]) {
+    let mut seen: HashMap<(String, String), u32> = HashMap::new();
+    for entry in passwords.iter_mut() {
+        if !entry.username.is_empty() || uuid::Uuid::parse_str(&entry.id).is_ok() {
+            continue;
+        }
+        // Identical entries still get distinct ids
+        let count = seen.entry((entry.name.clone(), entry.id.clone())).or_insert(0);
+        let seed = format!("{}\0{}\0{}", entry.name, entry.id, count);
+        *count += 1;
+        entry.username = std::mem::take(&mut entry.id);
+        entry.id = uuid::Uuid::new_v5(&LEGACY_ENTRY_NAMESPACE, seed.as_bytes()).to_string();
+    }
+}
+
+// Generation of the vault currently on disk, if there is an encrypted vault there
+fn read_disk_generation(file_path: &Path) -> Result<Option<u64>, String> {
+    if !file_path.exists() {
+        return Ok(None);
+    }
+
+    let contents =
+        std::fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+
+    // An unencrypted legacy file is about to be replaced by its encrypted form
+    if contents.trim_start().starts_with('[') {
+        return Ok(None);
+    }
+
+    Ok(Some(parse_encrypted_file(&contents)?.header.generation))
+}
+
+// Change the master password. The vault always gets a fresh data key and is
+// re-encrypted, so neither old backups nor a leaked data key open it afterwards; the
+// backups the old key opens are moved to the new one. Only
+// the slot the old password opened gets the new password; every other slot is kept and
+// re-wrapped with the new key, which takes its secret from `other_secrets`. Slots
+// without a matching secret make the change fail and are named in the error.
+pub fn change_master_password(
+    file_path: &Path,
+    old_password: &str,
+    new_password: &str,
+    key_file: Option<&[u8]>,
+    other_secrets: &[UnlockSecret],
+) -> PasswordDataResult {
+    let _lock = VaultLock::acquire(file_path)
+        .map_err(|e| format!("Failed to lock vault: {}", e))?;
+
+    let encrypted = read_encrypted_file(file_path)?;
+    let (old_key, opened) =
+        unlock_header_slot(&encrypted.header, &UnlockSecret::Password(old_password, key_file))
+            .map_err(|_| "Current master password is incorrect".to_string())?;
+    let passwords = decrypt_entries(&encrypted, &old_key)
+        .map_err(|_| "Current master password is incorrect".to_string())?;
+
+    // Legacy vaults have no slots; the new one is their first
+    let mut old_slots = encrypted.header.key_slots.clone();
+    let (position, label) = match opened {
+        Some(i) => (i, old_slots.remove(i).label),
+        None => (0, master_password_label(key_file).to_string()),
+    };
+
+    let key = generate_data_key();
+    let mut key_slots = Vec::new();
+    let mut missing = Vec::new();
+    for slot in &old_slots {
+        let secret = other_secrets
+            .iter()
+            .find(|secret| open_key_slot(slot, secret).is_some_and(|unwrapped| unwrapped == old_key));
+        match secret {
+            Some(secret) => {
+                // Same slot, same id; only the wrapped key changes
+                let rewrapped = create_key_slot(&key, secret, &slot.label)?;
+                key_slots.push(KeySlot {
+                    id: slot.id.clone(),
+                    created: slot.created.clone(),
+                    ..rewrapped
+                });
+            }
+            None => missing.push(format!("{} ({})", slot.id, slot.label)),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(format!(
+            "The vault gets a new key, and these key slots need their secret to move to it: {}. \
+             Give it to `rsp passwd`, or remove the slots with `rsp key remove <id>` and add them again afterwards",
+            missing.join(", ")
+        ));
+    }
+
+    let new_secret = UnlockSecret::Password(new_password, key_file);
+    let password_slot = create_key_slot(&key, &new_secret, &label)?;
+    key_slots.insert(position, password_slot);
+
+    let mut header = VaultHeader {
+        cipher: encrypted.header.cipher,
+        generation: encrypted.header.generation,
+        revision: encrypted.header.revision,
+        ..VaultHeader::new(key_slots)
+    };
+    write_vault(file_path, &passwords, &key, &mut header)?;
+
+    // Backups taken with the old key would still open with the old password
+    match rekey_backups(file_path, &old_key, &key, &header) {
+        Ok(moved) => log::info!("Moved {} backups to the new vault key", moved),
+        Err(e) => log::warn!("Failed to move backups to the new vault key: {}", e),
+    }
+
+    Ok((passwords, key, header))
+}
+
+// Add or remove key slots; the data key must open the vault so no slot wraps a wrong key
+pub fn update_key_slots<T, F>(
+    file_path: &Path,
+    key: &[u8; 32],
+    header: &mut VaultHeader,
+    change: F,
+) -> Result<T, String>
+where
+    F: FnOnce(&mut Vec<KeySlot>) -> Result<T, String>,
+{
+    let _lock = VaultLock::acquire(file_path)
+        .map_err(|e| format!("Failed to lock vault: {}", e))?;
+
+    let encrypted = read_encrypted_file(file_path)?;
+    if encrypted.header.is_legacy() {
+        return Err("Unlock the vault with the master password once to upgrade it first".into());
+    }
+    let passwords = decrypt_entries(&encrypted, key)
+        .map_err(|_| "Vault was re-keyed by another process. Please unlock again.".to_string())?;
+
+    let mut disk_header = encrypted.header.clone();
+    let result = change(&mut disk_header.key_slots)?;
+    if disk_header.key_slots.is_empty() {
+        return Err("Cannot remove the last key slot".into());
+    }
+
+    write_vault(file_path, &passwords, key, &mut disk_header)?;
+    *header = disk_header;
+    Ok(result)
+}
+
+// Random salt for a new key derivation
+pub fn generate_salt() -> Vec<u8> {
     let mut salt = [0u8; 16];
     for byte in &mut salt {
         *byte = rand::random();
     }
-    let key = derive_key(password, &salt)?;
-    let passwords = Vec::new();
-    save_passwords(file_path, &passwords, &key, &salt)?;
-    Ok((passwords, key, salt.to_vec()))
+    salt.to_vec()
+}
+
+// label of the slot holding the master password:
+pub fn master_password_label(key_file: Option<&[u8]>) -> &'static str {
+    if key_file.is_some() {
+        "master password + key file"
+    } else {
+        "master password"
+    }
+}
+
+// Create a new vault holding `passwords`, protected by a master password slot and
+// encrypted with `cipher` for its whole life
+pub fn create_vault(
+    file_path: &Path,
+    password: &str,
+    key_file: Option<&[u8]>,
+    cipher: CipherKind,
+    passwords: Vec<PasswordEntry>,
+) -> PasswordDataResult {
+    let key = generate_data_key();
+    let secret = UnlockSecret::Password(password, key_file);
+    let slot = create_key_slot(&key, &secret, master_password_label(key_file))?;
+    let mut header = VaultHeader {
+        cipher,
+        ..VaultHeader::new(vec![slot])
+    };
+    save_passwords(file_path, &passwords, &key, &mut header)?;
+    Ok((passwords, key, header))
+}
+
+pub fn create_password_file(file_path: &Path, password: &str) -> PasswordDataResult {
+    create_vault(file_path, password, None, SETTINGS.vault_cipher, Vec::new())
 }
 
 pub fn load_passwords_with_key(
-    file_path: &str,
+    file_path: &Path,
     key: &[u8; 32],
-    _salt: &[u8],
 ) -> io::Result<Vec<PasswordEntry>> {
     let mut file = File::open(file_path).map_err(|e| {
         io::Error::new(
@@ -122,36 +554,12 @@ pub fn load_passwords_with_key(
         )
     })?;
 
-    let encrypted: EncryptedFile = serde_json::from_str(&contents).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!("Invalid file format: {}", e),
-        )
-    })?;
-
-    let nonce = STANDARD
-        .decode(&encrypted.nonce)
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Invalid nonce: {}", e)))?;
-
-    let encrypted_data = STANDARD.decode(&encrypted.encrypted_data).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!("Invalid encrypted data: {}", e),
-        )
-    })?;
+    let encrypted = parse_encrypted_file(&contents)
+        .and_then(|encrypted| check_revision(file_path, &encrypted.header).map(|_| encrypted))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
 
-    let decrypted = decrypt_data(&encrypted_data, key, &nonce).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!("Decryption failed: {}", e),
-        )
-    })?;
-
-    let passwords: Vec<PasswordEntry> = serde_json::from_slice(&decrypted).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!("Failed to parse passwords: {}", e),
-        )
+    let passwords = decrypt_entries(&encrypted, key).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("Decryption failed: {}", e))
     })?;
 
     Ok(passwords)
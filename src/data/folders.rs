@@ -0,0 +1,85 @@
+use std::collections::BTreeSet;
+
+use crate::models::structs::{PasswordEntry, Timestamps};
+
+// `/work//aws/ ` -> `work/aws`:
+pub fn normalize_folder(path: &str) -> String {
+    path.split('/')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// tags from a comma separated list, trimmed and without duplicates:
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+        if !tags.iter().any(|existing| existing.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+// whether `entry_folder` is `folder` or one of its subfolders; every entry is in the root:
+pub fn is_in_folder(entry_folder: &str, folder: &str) -> bool {
+    folder.is_empty()
+        || entry_folder == folder
+        || entry_folder
+            .strip_prefix(folder)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+// Every folder holding live entries, along with its parents, sorted so each folder comes
+// right before its subfolders
+pub fn list_folders(passwords: &[PasswordEntry]) -> Vec<String> {
+    let mut folders = BTreeSet::new();
+    for entry in passwords.iter().filter(|entry| !entry.is_trashed()) {
+        let mut path = String::new();
+        for segment in entry.folder.split('/').filter(|s| !s.is_empty()) {
+            if !path.is_empty() {
+                path.push('/');
+            }
+            path.push_str(segment);
+            folders.insert(path.clone());
+        }
+    }
+    // Sort on segments so `a/b` stays with `a`, ahead of `a-c`
+    let mut folders: Vec<String> = folders.into_iter().collect();
+    folders.sort_by(|a, b| a.split('/').cmp(b.split('/')));
+    folders
+}
+
+// Move the folder `from`, with its subfolders, to `to` (the root when empty). Returns
+// the number of entries that moved.
+pub fn rename_folder(passwords: &mut [PasswordEntry], from: &str, to: &str) -> Result<usize, String> {
+    let from = normalize_folder(from);
+    let to = normalize_folder(to);
+    if from.is_empty() {
+        return Err("Name the folder to move".into());
+    }
+    if from == to {
+        return Ok(0);
+    }
+    if is_in_folder(&to, &from) {
+        return Err(format!("Cannot move '{}' into itself", from));
+    }
+
+    let mut moved = 0;
+    let now = Timestamps::now();
+    // Trashed entries keep the folder they were deleted from
+    for entry in passwords
+        .iter_mut()
+        .filter(|entry| !entry.is_trashed() && is_in_folder(&entry.folder, &from))
+    {
+        let rest = &entry.folder[from.len()..];
+        entry.folder = normalize_folder(&format!("{}{}", to, rest));
+        entry.times.modified = now.clone();
+        moved += 1;
+    }
+    if moved == 0 {
+        return Err(format!("Folder '{}' not found", from));
+    }
+    Ok(moved)
+}
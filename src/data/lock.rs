@@ -0,0 +1,48 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+/// Exclusive advisory lock (flock) held for the duration of a vault
+/// read-modify-write. Released when dropped.
+pub struct VaultLock {
+    file: File,
+}
+
+// The lock lives in a separate file because the vault itself is replaced by rename
+pub fn get_lock_path(vault_path: &Path) -> PathBuf {
+    vault_path.with_extension("lock")
+}
+
+impl VaultLock {
+    // block until the lock on the vault is acquired:
+    pub fn acquire(vault_path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .mode(0o600)
+            .open(get_lock_path(vault_path))?;
+
+        loop {
+            let ret = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) };
+            if ret == 0 {
+                return Ok(Self { file });
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+}
+
+impl Drop for VaultLock {
+    fn drop(&mut self) {
+        unsafe {
+            libc::flock(self.file.as_raw_fd(), libc::LOCK_UN);
+        }
+    }
+}
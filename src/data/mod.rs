@@ -1 +1,8 @@
-pub mod data;
\ No newline at end of file
+pub mod backup;
+pub mod data;
+pub mod folders;
+pub mod lock;
+pub mod revision;
+pub mod trash;
+pub mod vault;
+pub mod verify;
\ No newline at end of file
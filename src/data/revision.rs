@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::data::lock::VaultLock;
+use crate::data::vault::get_state_dir;
+use crate::models::structs::{VaultHeader, AUTHENTICATED_HEADER_VERSION};
+use crate::utils::atomic_file::write_atomic;
+
+// File in the state dir mapping each vault file to the newest revision seen
+const REVISIONS_FILE_NAME: &str = "revisions.json";
+
+// Set by `--accept-rollback`: open a vault older than the last one seen anyway
+static ACCEPT_ROLLBACK: AtomicBool = AtomicBool::new(false);
+
+// accept older vault revisions for the rest of this process:
+pub fn set_accept_rollback(accept: bool) {
+    ACCEPT_ROLLBACK.store(accept, Ordering::SeqCst);
+}
+
+pub fn accept_rollback() -> bool {
+    ACCEPT_ROLLBACK.load(Ordering::SeqCst)
+}
+
+pub fn get_revisions_path() -> PathBuf {
+    get_state_dir().join(REVISIONS_FILE_NAME)
+}
+
+// Revision of a vault, counting only when the header is authenticated: in older
+// formats anybody could have written any number there
+pub fn authenticated_revision(header: &VaultHeader) -> u64 {
+    if header.version >= AUTHENTICATED_HEADER_VERSION {
+        header.revision
+    } else {
+        0
+    }
+}
+
+// the same vault may be reached through different paths:
+fn revision_key(vault_path: &Path) -> String {
+    fs::canonicalize(vault_path)
+        .unwrap_or_else(|_| vault_path.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn read_revisions(path: &Path) -> io::Result<BTreeMap<String, u64>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e),
+    }
+}
+
+// newest revision of the vault this user has opened or written, if any:
+pub fn last_seen_revision(vault_path: &Path) -> Option<u64> {
+    match read_revisions(&get_revisions_path()) {
+        Ok(revisions) => revisions.get(&revision_key(vault_path)).copied(),
+        Err(e) => {
+            log::warn!("Failed to read vault revisions: {}", e);
+            None
+        }
+    }
+}
+
+// remember `revision` as the newest one of the vault, even if it is lower:
+pub fn record_revision(vault_path: &Path, revision: u64) -> io::Result<()> {
+    let path = get_revisions_path();
+    let _lock = VaultLock::acquire(&path)?;
+    let mut revisions = read_revisions(&path)?;
+    revisions.insert(revision_key(vault_path), revision);
+    let contents = serde_json::to_vec_pretty(&revisions)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    write_atomic(&path, &contents)
+}
+
+// Refuse a vault older than the last one seen: an attacker or a bad sync may have
+// put back an old copy that still decrypts
+pub fn check_revision(vault_path: &Path, header: &VaultHeader) -> Result<(), String> {
+    let revision = authenticated_revision(header);
+    let last_seen = match last_seen_revision(vault_path) {
+        Some(last_seen) if revision < last_seen => last_seen,
+        _ => return Ok(()),
+    };
+
+    let message = format!(
+        "Vault {} is at revision {}, but revision {} was already seen. \
+         The file may have been replaced with an older copy.",
+        vault_path.display(),
+        revision,
+        last_seen
+    );
+    if !accept_rollback() {
+        log::warn!("Refusing rolled back vault: {}", message);
+        return Err(format!(
+            "{} Check where it came from, then run with --accept-rollback to use it anyway.",
+            message
+        ));
+    }
+
+    log::warn!("Accepting rolled back vault: {}", message);
+    eprintln!("WARNING: {}", message);
+    eprintln!("WARNING: Continuing because of --accept-rollback.");
+    Ok(())
+}
+
+// Remember the revision of a vault that was just decrypted or written. It only moves
+// forward, unless an older revision was explicitly accepted.
+pub fn note_revision(vault_path: &Path, header: &VaultHeader) {
+    let revision = authenticated_revision(header);
+    match last_seen_revision(vault_path) {
+        Some(last_seen) if revision == last_seen => return,
+        Some(last_seen) if revision < last_seen && !accept_rollback() => return,
+        _ => {}
+    }
+    if let Err(e) = record_revision(vault_path, revision) {
+        log::warn!("Failed to record revision of {:?}: {}", vault_path, e);
+    }
+}
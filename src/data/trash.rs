@@ -0,0 +1,74 @@
+use crate::models::structs::{PasswordEntry, Timestamps};
+
+// Deleting an entry only stamps it with the time it was moved to the trash. Trashed
+// entries stay in the vault, out of every list, until they are restored, purged by
+// hand, or dropped on a save once they are older than the retention period.
+
+// move the live entries `matches` picks to the trash, returning how many moved:
+pub fn move_to_trash<F>(passwords: &mut [PasswordEntry], matches: F) -> usize
+where
+    F: Fn(&PasswordEntry) -> bool,
+{
+    let now = Timestamps::now();
+    let mut moved = 0;
+    for entry in passwords.iter_mut().filter(|entry| !entry.is_trashed() && matches(entry)) {
+        entry.times.deleted = now.clone();
+        moved += 1;
+    }
+    moved
+}
+
+// trashed entries, most recently deleted first:
+pub fn list_trash(passwords: &[PasswordEntry]) -> Vec<&PasswordEntry> {
+    let mut trash: Vec<&PasswordEntry> = passwords.iter().filter(|entry| entry.is_trashed()).collect();
+    trash.sort_by(|a, b| b.times.deleted.cmp(&a.times.deleted));
+    trash
+}
+
+// Split the entries of a vault into the live ones and those in the trash
+pub fn split_trash(passwords: Vec<PasswordEntry>) -> (Vec<PasswordEntry>, Vec<PasswordEntry>) {
+    let (mut trash, live): (Vec<_>, Vec<_>) = passwords.into_iter().partition(|entry| entry.is_trashed());
+    trash.sort_by(|a, b| b.times.deleted.cmp(&a.times.deleted));
+    (live, trash)
+}
+
+// put the trashed entry `id` back among the live ones:
+pub fn restore_from_trash(passwords: &mut [PasswordEntry], id: &str) -> Result<(), String> {
+    let entry = passwords
+        .iter_mut()
+        .find(|entry| entry.id == id && entry.is_trashed())
+        .ok_or("Entry is no longer in the trash")?;
+    entry.times.deleted.clear();
+    Ok(())
+}
+
+// delete the trashed entry `id` for good:
+pub fn purge_from_trash(passwords: &mut Vec<PasswordEntry>, id: &str) -> Result<(), String> {
+    let count = passwords.len();
+    passwords.retain(|entry| !(entry.id == id && entry.is_trashed()));
+    if passwords.len() == count {
+        return Err("Entry is no longer in the trash".into());
+    }
+    Ok(())
+}
+
+// delete everything in the trash for good, returning how many entries went:
+pub fn empty_trash(passwords: &mut Vec<PasswordEntry>) -> usize {
+    let count = passwords.len();
+    passwords.retain(|entry| !entry.is_trashed());
+    count - passwords.len()
+}
+
+// Drop entries that have been in the trash for more than `retention_days` (0 keeps them
+// until the trash is emptied), returning how many went
+pub fn purge_expired(passwords: &mut Vec<PasswordEntry>, retention_days: u64) -> usize {
+    if retention_days == 0 {
+        return 0;
+    }
+    let count = passwords.len();
+    passwords.retain(|entry| {
+        !entry.is_trashed()
+            || Timestamps::days_since(&entry.times.deleted).is_none_or(|days| days < retention_days as i64)
+    });
+    count - passwords.len()
+}
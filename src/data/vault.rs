@@ -0,0 +1,182 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::utils::atomic_file::write_atomic;
+
+/// Name of the vault used when neither `--vault` nor `RSP_VAULT` is given.
+pub const DEFAULT_VAULT_NAME: &str = "default";
+
+/// Environment variable selecting the active vault.
+pub const VAULT_ENV_VAR: &str = "RSP_VAULT";
+
+/// File older versions kept the vault in, relative to the working directory.
+pub const LEGACY_VAULT_FILE: &str = "passwords.json";
+
+// Vault explicitly selected on the command line (takes precedence over the env var)
+lazy_static::lazy_static! {
+    static ref ACTIVE_VAULT: Mutex<Option<String>> = Mutex::new(None);
+}
+
+// check that a vault name is usable as a file name:
+pub fn validate_vault_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Vault name cannot be empty".into());
+    }
+    if name.starts_with('.') {
+        return Err(format!("Invalid vault name '{}': cannot start with '.'", name));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.') {
+        return Err(format!(
+            "Invalid vault name '{}': only letters, digits, '-', '_' and '.' are allowed",
+            name
+        ));
+    }
+    Ok(())
+}
+
+// select the vault for the rest of this process:
+pub fn set_active_vault(name: &str) -> Result<(), String> {
+    validate_vault_name(name)?;
+    let mut active = ACTIVE_VAULT.lock().map_err(|e| e.to_string())?;
+    *active = Some(name.to_string());
+    Ok(())
+}
+
+// name of the vault in use: `--vault`, then `RSP_VAULT`, then the default:
+pub fn active_vault_name() -> String {
+    if let Ok(active) = ACTIVE_VAULT.lock() {
+        if let Some(name) = active.as_ref() {
+            return name.clone();
+        }
+    }
+
+    match std::env::var(VAULT_ENV_VAR) {
+        Ok(name) if !name.is_empty() => {
+            if let Err(e) = validate_vault_name(&name) {
+                log::warn!("Ignoring {}: {}", VAULT_ENV_VAR, e);
+                DEFAULT_VAULT_NAME.to_string()
+            } else {
+                name
+            }
+        }
+        _ => DEFAULT_VAULT_NAME.to_string(),
+    }
+}
+
+// Base directory for everything rustpass keeps under the XDG data dir
+pub fn get_data_dir() -> PathBuf {
+    let base_dir = dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join("rustpass");
+    create_private_dir(&base_dir);
+    base_dir
+}
+
+// Directory holding every vault file
+pub fn get_vaults_dir() -> PathBuf {
+    let path = get_data_dir().join("vaults");
+    create_private_dir(&path);
+    path
+}
+
+// Path of the named vault file
+pub fn get_vault_path_for(name: &str) -> PathBuf {
+    get_vaults_dir().join(format!("{}.json", name))
+}
+
+// Path of the active vault file
+pub fn get_vault_path() -> PathBuf {
+    get_vault_path_for(&active_vault_name())
+}
+
+// Directory for unlock state and logs
+pub fn get_state_dir() -> PathBuf {
+    let path = get_data_dir().join("state");
+    create_private_dir(&path);
+    path
+}
+
+// Per-vault directory for cached unlock state
+pub fn get_vault_state_dir() -> PathBuf {
+    let path = get_state_dir().join(active_vault_name());
+    create_private_dir(&path);
+    path
+}
+
+// list the names of all vaults on disk:
+pub fn list_vaults() -> io::Result<Vec<String>> {
+    let mut names: Vec<String> = fs::read_dir(get_vaults_dir())?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                return None;
+            }
+            path.file_stem().and_then(|s| s.to_str()).map(String::from)
+        })
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+// a vault left in the working directory by older versions, as long as the default
+// vault is selected and hasn't been created yet:
+pub fn find_legacy_vault() -> Option<PathBuf> {
+    if active_vault_name() != DEFAULT_VAULT_NAME || get_vault_path().exists() {
+        return None;
+    }
+    let path = std::env::current_dir().ok()?.join(LEGACY_VAULT_FILE);
+    if path.is_file() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+// hint printed when a legacy vault is found instead of the default vault:
+pub fn legacy_vault_hint(legacy: &Path) -> String {
+    format!(
+        "Found a vault from an older version at {}. Move it to {} to keep using it.",
+        legacy.display(),
+        get_vault_path_for(DEFAULT_VAULT_NAME).display()
+    )
+}
+
+// move a legacy vault into place as the default vault:
+pub fn import_legacy_vault(legacy: &Path) -> io::Result<PathBuf> {
+    let target = get_vault_path_for(DEFAULT_VAULT_NAME);
+    if target.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", target.display()),
+        ));
+    }
+
+    if fs::rename(legacy, &target).is_err() {
+        // The data dir is on another filesystem: copy, then drop the original
+        let data = fs::read(legacy)?;
+        write_atomic(&target, &data)?;
+        fs::remove_file(legacy)?;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(target)
+}
+
+// create a directory readable only by the owner:
+fn create_private_dir(path: &Path) {
+    fs::create_dir_all(path).expect("Failed to create rustpass data directory");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let perms = fs::Permissions::from_mode(0o700);
+        let _ = fs::set_permissions(path, perms);
+    }
+}
@@ -0,0 +1,172 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::data::data::{decrypt_entries, parse_encrypted_file};
+use crate::data::revision::check_revision;
+use crate::encryption::cipher::cipher_for;
+use crate::models::structs::{
+    EncryptedFile, KdfParams, VaultHeader, AUTHENTICATED_HEADER_VERSION, VAULT_FORMAT_VERSION,
+};
+use crate::models::types::EncryptionKey;
+
+// AES-GCM nonce and wrapped 256-bit key plus tag, as written by `create_key_slot`
+const SLOT_NONCE_LEN: usize = 12;
+const WRAPPED_KEY_LEN: usize = 32 + 16;
+const TAG_LEN: usize = 16;
+
+/// What `verify_vault` found wrong with a vault file.
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    /// Damage or tampering; the file cannot be trusted.
+    pub errors: Vec<String>,
+    /// Worth knowing but harmless, e.g. an older format.
+    pub warnings: Vec<String>,
+    /// Number of entries, once the payload was decrypted and parsed.
+    pub entries: Option<usize>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+// Check a vault file. The layout is checked first; if it is sound, `unlock` is asked
+// for the data key so the entries and the authenticated header can be checked too.
+pub fn verify_vault<F>(file_path: &Path, unlock: F) -> VerifyReport
+where
+    F: FnOnce(&VaultHeader) -> Result<EncryptionKey, String>,
+{
+    let mut report = VerifyReport::default();
+
+    let contents = match std::fs::read_to_string(file_path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            report.errors.push(format!("Cannot read file: {}", e));
+            return report;
+        }
+    };
+    let encrypted = match parse_encrypted_file(&contents) {
+        Ok(encrypted) => encrypted,
+        Err(e) => {
+            report.errors.push(e);
+            return report;
+        }
+    };
+
+    check_header(&encrypted.header, &mut report);
+    check_payload_layout(&encrypted, &mut report);
+    if let Err(e) = check_revision(file_path, &encrypted.header) {
+        report.errors.push(e);
+    }
+    if !report.is_ok() {
+        return report;
+    }
+
+    let key = match unlock(&encrypted.header) {
+        Ok(key) => key,
+        Err(e) => {
+            report.errors.push(format!("Cannot unlock vault: {}", e));
+            return report;
+        }
+    };
+
+    match decrypt_entries(&encrypted, &key) {
+        Ok(entries) => report.entries = Some(entries.len()),
+        // Without key slots a wrong password cannot be told apart from tampering
+        Err(e) if e.starts_with("Decryption failed") && encrypted.header.is_legacy() => report
+            .errors
+            .push("Decryption failed: wrong master password or the entries were modified".to_string()),
+        Err(e) if e.starts_with("Decryption failed") => {
+            report.errors.push(if encrypted.header.version >= AUTHENTICATED_HEADER_VERSION {
+                "Authentication failed: the header or the entries were modified".to_string()
+            } else {
+                "Authentication failed: the entries were modified".to_string()
+            })
+        }
+        Err(e) => report.errors.push(e),
+    }
+    report
+}
+
+fn check_header(header: &VaultHeader, report: &mut VerifyReport) {
+    if header.version < AUTHENTICATED_HEADER_VERSION {
+        report.warnings.push(format!(
+            "Format version {} does not authenticate the header; it is upgraded to version {} on the next save",
+            header.version, VAULT_FORMAT_VERSION
+        ));
+    }
+
+    if header.is_legacy() {
+        report
+            .warnings
+            .push("No key slots yet; unlock the vault once to upgrade it".to_string());
+        match &header.salt {
+            Some(salt) if !salt.is_empty() => {}
+            _ => report.errors.push("Vault has neither key slots nor a salt".to_string()),
+        }
+        if let Some(kdf) = &header.kdf {
+            check_kdf("Header", kdf, report);
+        }
+        return;
+    }
+
+    let mut ids = HashSet::new();
+    for slot in &header.key_slots {
+        let name = format!("Key slot {}", slot.id);
+        if !ids.insert(&slot.id) {
+            report.errors.push(format!("Duplicate key slot {}", slot.id));
+        }
+        if slot.salt.is_empty() {
+            report.errors.push(format!("{} has an empty salt", name));
+        }
+        if slot.nonce.len() != SLOT_NONCE_LEN {
+            report.errors.push(format!(
+                "{} has a {}-byte nonce (expected {})",
+                name,
+                slot.nonce.len(),
+                SLOT_NONCE_LEN
+            ));
+        }
+        if slot.wrapped_key.len() != WRAPPED_KEY_LEN {
+            report.errors.push(format!(
+                "{} has a {}-byte wrapped key (expected {})",
+                name,
+                slot.wrapped_key.len(),
+                WRAPPED_KEY_LEN
+            ));
+        }
+        check_kdf(&name, &slot.kdf, report);
+    }
+}
+
+fn check_kdf(name: &str, kdf: &KdfParams, report: &mut VerifyReport) {
+    if let Err(e) = argon2::Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, None) {
+        report
+            .errors
+            .push(format!("{} has invalid KDF parameters: {}", name, e));
+    }
+}
+
+fn check_payload_layout(encrypted: &EncryptedFile, report: &mut VerifyReport) {
+    let cipher = cipher_for(encrypted.header.cipher);
+
+    match STANDARD.decode(&encrypted.nonce) {
+        Ok(nonce) if nonce.len() != cipher.nonce_len() => report.errors.push(format!(
+            "Entry nonce is {} bytes, but the cipher needs {}",
+            nonce.len(),
+            cipher.nonce_len()
+        )),
+        Ok(_) => {}
+        Err(e) => report.errors.push(format!("Invalid nonce: {}", e)),
+    }
+
+    match STANDARD.decode(&encrypted.encrypted_data) {
+        Ok(data) if data.len() < TAG_LEN => report
+            .errors
+            .push("Encrypted entries are truncated".to_string()),
+        Ok(_) => {}
+        Err(e) => report.errors.push(format!("Invalid encrypted data: {}", e)),
+    }
+}
@@ -0,0 +1,111 @@
+use aes_gcm::{
+    aead::{Aead, Payload},
+    Aes256Gcm, KeyInit,
+};
+use chacha20poly1305::XChaCha20Poly1305;
+use rand::RngCore;
+
+use crate::models::structs::CipherKind;
+use crate::models::types::{DecryptedDataResult, EncryptedDataResult};
+
+/// An AEAD cipher with a 256-bit key and a random nonce per message.
+///
+/// `aad` is authenticated along with the message but not encrypted; decryption fails
+/// unless exactly the same bytes are passed again.
+pub trait Cipher: Sync {
+    /// Length of the nonce stored next to every ciphertext.
+    fn nonce_len(&self) -> usize;
+
+    /// Fails, rather than panicking, if `nonce` is not `nonce_len()` bytes long.
+    fn encrypt(&self, key: &[u8; 32], nonce: &[u8], data: &[u8], aad: &[u8]) -> Result<Vec<u8>, String>;
+
+    /// Fails, rather than panicking, if `nonce` is not `nonce_len()` bytes long.
+    fn decrypt(&self, key: &[u8; 32], nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> DecryptedDataResult;
+}
+
+// Nonces of the wrong length are an error, not a panic in the AEAD crates
+fn check_nonce(cipher: &dyn Cipher, nonce: &[u8], operation: &str) -> Result<(), String> {
+    if nonce.len() != cipher.nonce_len() {
+        return Err(format!(
+            "{} failed: expected a {}-byte nonce, got {} bytes",
+            operation,
+            cipher.nonce_len(),
+            nonce.len()
+        ));
+    }
+    Ok(())
+}
+
+/// AES-256-GCM with 96-bit nonces.
+pub struct Aes256GcmCipher;
+
+impl Cipher for Aes256GcmCipher {
+    fn nonce_len(&self) -> usize {
+        12
+    }
+
+    fn encrypt(&self, key: &[u8; 32], nonce: &[u8], data: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+        check_nonce(self, nonce, "Encryption")?;
+        Aes256Gcm::new(key.into())
+            .encrypt(nonce.into(), Payload { msg: data, aad })
+            .map_err(|e| format!("Encryption failed: {}", e))
+    }
+
+    fn decrypt(&self, key: &[u8; 32], nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> DecryptedDataResult {
+        check_nonce(self, nonce, "Decryption")?;
+        Aes256Gcm::new(key.into())
+            .decrypt(nonce.into(), Payload { msg: ciphertext, aad })
+            .map_err(|e| format!("Decryption failed: {}", e))
+    }
+}
+
+/// XChaCha20-Poly1305 with 192-bit nonces, safe to pick at random for any number of saves.
+pub struct XChaCha20Poly1305Cipher;
+
+impl Cipher for XChaCha20Poly1305Cipher {
+    fn nonce_len(&self) -> usize {
+        24
+    }
+
+    fn encrypt(&self, key: &[u8; 32], nonce: &[u8], data: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+        check_nonce(self, nonce, "Encryption")?;
+        XChaCha20Poly1305::new(key.into())
+            .encrypt(nonce.into(), Payload { msg: data, aad })
+            .map_err(|e| format!("Encryption failed: {}", e))
+    }
+
+    fn decrypt(&self, key: &[u8; 32], nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> DecryptedDataResult {
+        check_nonce(self, nonce, "Decryption")?;
+        XChaCha20Poly1305::new(key.into())
+            .decrypt(nonce.into(), Payload { msg: ciphertext, aad })
+            .map_err(|e| format!("Decryption failed: {}", e))
+    }
+}
+
+// implementation of the cipher recorded in a vault header:
+pub fn cipher_for(kind: CipherKind) -> &'static dyn Cipher {
+    match kind {
+        CipherKind::Aes256Gcm => &Aes256GcmCipher,
+        CipherKind::XChaCha20Poly1305 => &XChaCha20Poly1305Cipher,
+    }
+}
+
+// Encrypt data under a fresh random nonce
+pub fn encrypt_with(kind: CipherKind, data: &[u8], aad: &[u8], key: &[u8; 32]) -> EncryptedDataResult {
+    let cipher = cipher_for(kind);
+    let mut nonce = vec![0u8; cipher.nonce_len()];
+    rand::rng().fill_bytes(&mut nonce);
+    let ciphertext = cipher.encrypt(key, &nonce, data, aad)?;
+    Ok((nonce, ciphertext))
+}
+
+// Decrypt data; nonces of the wrong length are rejected by the cipher
+pub fn decrypt_with(
+    kind: CipherKind,
+    ciphertext: &[u8],
+    aad: &[u8],
+    key: &[u8; 32],
+    nonce: &[u8],
+) -> DecryptedDataResult {
+    cipher_for(kind).decrypt(key, nonce, ciphertext, aad)
+}
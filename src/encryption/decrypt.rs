@@ -1,4 +1,5 @@
-use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit};
+use crate::encryption::cipher::decrypt_with;
+use crate::models::structs::CipherKind;
 
 use crate::models::types::DecryptedDataResult;
 
@@ -54,10 +55,7 @@ pub fn decode_codesmith28(encrypted: &str) -> String {
 }
 
 
-// Decrypt data
+// Decrypt data encrypted with `encrypt_data`
 pub fn decrypt_data(ciphertext: &[u8], key: &[u8; 32], nonce: &[u8]) -> DecryptedDataResult {
-    let cipher = Aes256Gcm::new(key.into());
-    cipher
-        .decrypt(nonce.into(), ciphertext)
-        .map_err(|e| format!("Decryption failed: {}", e))
+    decrypt_with(CipherKind::Aes256Gcm, ciphertext, &[], key, nonce)
 }
\ No newline at end of file
@@ -1,5 +1,5 @@
-use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit};
-
+use crate::encryption::cipher::encrypt_with;
+use crate::models::structs::CipherKind;
 use crate::models::types::EncryptedDataResult;
 
 // list of numbers:
@@ -58,15 +58,7 @@ pub fn codesmith28(string: &str) -> String {
     x
 }
 
-// Encrypt data
+// Encrypt data with AES-256-GCM (key slots and state files; vault entries use the vault's cipher)
 pub fn encrypt_data(data: &[u8], key: &[u8; 32]) -> EncryptedDataResult {
-    let cipher = Aes256Gcm::new(key.into());
-    let mut nonce = [0u8; 12];
-    for byte in &mut nonce {
-        *byte = rand::random();
-    }
-    let ciphertext = cipher
-        .encrypt(&nonce.into(), data)
-        .map_err(|e| format!("Encryption failed: {}", e))?;
-    Ok((nonce.to_vec(), ciphertext))
+    encrypt_with(CipherKind::Aes256Gcm, data, &[], key)
 }
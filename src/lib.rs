@@ -7,5 +7,5 @@ pub mod data;
 pub mod state;
 pub mod cli;
 pub mod daemon;
+pub mod config;
 
-pub const PASSWORD_FILE_PATH: &str = "./passwords.json";
@@ -1,16 +1,30 @@
 use std::io;
 
 use rustpass::{
-    cli::handler::{ handle_command, parse_args, Command },
+    auth::keyfile::set_key_file,
+    cli::handler::{ extract_global_options, handle_command, parse_args, Command },
     daemon::service::DaemonService,
+    data::{ revision::set_accept_rollback, vault::set_active_vault },
     daemon::ipc,
     tui::{ self, run_tui },
-    utils::logger::init_logger,
+    utils::{
+        hardening::init_hardening,
+        logger::{ init_logger, DAEMON_LOG_FILE_NAME, LOG_FILE_NAME },
+    },
 };
 
 fn main() -> io::Result<()> {
+    // Parse command line arguments
+    let mut args: Vec<String> = std::env::args().collect();
+
+    // Special case for daemon mode (started by daemonize)
+    let daemon_mode = args.len() >= 2 && args[1] == "--daemon-direct-start";
+
     // Initialize logging
-    init_logger();
+    init_logger(if daemon_mode { DAEMON_LOG_FILE_NAME } else { LOG_FILE_NAME });
+
+    // Keep secrets out of core dumps, swap and other processes (the daemon inherits this)
+    init_hardening()?;
 
     // Clean up any stale sockets
     let _ = ipc::cleanup_stale_socket();
@@ -24,23 +38,24 @@ fn main() -> io::Result<()> {
         })
     );
 
-    // Parse command line arguments
-    let args: Vec<String> = std::env::args().collect();
-
-    // Special case for daemon mode (started by daemonize)
-    if args.len() >= 2 && args[1] == "--daemon-direct-start" {
-        // Initialize logging for daemon mode
-        env_logger::Builder
-            ::from_env(env_logger::Env::default())
-            .filter_level(log::LevelFilter::Info)
-            .format_timestamp_secs()
-            .init();
-
+    if daemon_mode {
         log::info!("Starting daemon in direct mode");
         let service = DaemonService::new();
         return service.start();
     }
 
+    // Apply options shared by every command
+    let options = extract_global_options(&mut args).map_err(|e|
+        io::Error::new(io::ErrorKind::InvalidInput, e)
+    )?;
+    if let Some(vault) = options.vault {
+        set_active_vault(&vault).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    }
+    if let Some(keyfile) = options.keyfile {
+        set_key_file(&keyfile)?;
+    }
+    set_accept_rollback(options.accept_rollback);
+
     // Normal CLI operation
     let command = parse_args(args);
 
@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::structs::FieldKind;
+
+/// What an entry stores. Logins use the username, password and URL of the entry;
+/// every other kind keeps its values in `PasswordEntry::details`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EntryKind {
+    #[default]
+    #[serde(rename = "login")]
+    Login,
+    #[serde(rename = "note")]
+    SecureNote,
+    #[serde(rename = "card")]
+    Card,
+    #[serde(rename = "identity")]
+    Identity,
+    #[serde(rename = "ssh-key")]
+    SshKey,
+    #[serde(rename = "api-credential")]
+    ApiCredential,
+}
+
+/// Extra check on a detail value; the error is appended to the field label.
+pub type FieldCheck = fn(&str) -> Result<(), String>;
+
+/// One value an entry kind asks for.
+pub struct DetailField {
+    /// Key in `PasswordEntry::details`.
+    pub key: &'static str,
+    pub label: &'static str,
+    pub kind: FieldKind,
+    pub required: bool,
+    /// Usually spans several lines, such as a PEM key.
+    pub multiline: bool,
+    /// Checks beyond what `kind` already validates.
+    pub check: Option<FieldCheck>,
+}
+
+const fn detail(key: &'static str, label: &'static str, kind: FieldKind) -> DetailField {
+    DetailField {
+        key,
+        label,
+        kind,
+        required: false,
+        multiline: false,
+        check: None,
+    }
+}
+
+const fn required(field: DetailField) -> DetailField {
+    DetailField { required: true, ..field }
+}
+
+const fn checked(field: DetailField, check: FieldCheck) -> DetailField {
+    DetailField { check: Some(check), ..field }
+}
+
+const fn multiline(field: DetailField) -> DetailField {
+    DetailField { multiline: true, ..field }
+}
+
+const CARD_FIELDS: &[DetailField] = &[
+    detail("cardholder", "Cardholder", FieldKind::Text),
+    required(checked(detail("number", "Card number", FieldKind::Hidden), check_card_number)),
+    required(checked(detail("expiry", "Expiry (MM/YY)", FieldKind::Text), check_expiry)),
+    checked(detail("cvv", "CVV", FieldKind::Hidden), check_cvv),
+];
+
+const IDENTITY_FIELDS: &[DetailField] = &[
+    required(detail("full_name", "Full name", FieldKind::Text)),
+    detail("email", "Email", FieldKind::Email),
+    detail("phone", "Phone", FieldKind::Text),
+    detail("address", "Address", FieldKind::Text),
+    detail("city", "City", FieldKind::Text),
+    detail("postal_code", "Postal code", FieldKind::Text),
+    detail("region", "State/region", FieldKind::Text),
+    detail("country", "Country", FieldKind::Text),
+];
+
+const SSH_KEY_FIELDS: &[DetailField] = &[
+    required(multiline(checked(
+        detail("private_key", "Private key", FieldKind::Hidden),
+        check_private_key,
+    ))),
+    checked(detail("public_key", "Public key", FieldKind::Text), check_public_key),
+    detail("passphrase", "Passphrase", FieldKind::Hidden),
+];
+
+const API_CREDENTIAL_FIELDS: &[DetailField] = &[
+    detail("key_id", "Key ID", FieldKind::Text),
+    required(detail("secret", "Secret", FieldKind::Hidden)),
+    detail("endpoint", "Endpoint", FieldKind::Url),
+];
+
+impl EntryKind {
+    pub const ALL: [EntryKind; 6] = [
+        EntryKind::Login,
+        EntryKind::SecureNote,
+        EntryKind::Card,
+        EntryKind::Identity,
+        EntryKind::SshKey,
+        EntryKind::ApiCredential,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryKind::Login => "login",
+            EntryKind::SecureNote => "note",
+            EntryKind::Card => "card",
+            EntryKind::Identity => "identity",
+            EntryKind::SshKey => "ssh-key",
+            EntryKind::ApiCredential => "api-credential",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EntryKind::Login => "Login",
+            EntryKind::SecureNote => "Secure note",
+            EntryKind::Card => "Payment card",
+            EntryKind::Identity => "Identity",
+            EntryKind::SshKey => "SSH key",
+            EntryKind::ApiCredential => "API credential",
+        }
+    }
+
+    // values stored in `details` for this kind, in form order:
+    pub fn fields(&self) -> &'static [DetailField] {
+        match self {
+            EntryKind::Login | EntryKind::SecureNote => &[],
+            EntryKind::Card => CARD_FIELDS,
+            EntryKind::Identity => IDENTITY_FIELDS,
+            EntryKind::SshKey => SSH_KEY_FIELDS,
+            EntryKind::ApiCredential => API_CREDENTIAL_FIELDS,
+        }
+    }
+
+    // the detail copied and printed when no field is named, if not the password:
+    pub fn primary_field(&self) -> Option<&'static str> {
+        match self {
+            EntryKind::Login | EntryKind::SecureNote | EntryKind::Identity => None,
+            EntryKind::Card => Some("number"),
+            EntryKind::SshKey => Some("private_key"),
+            EntryKind::ApiCredential => Some("secret"),
+        }
+    }
+
+    // the kind after this one, for cycling through them:
+    pub fn next(&self) -> EntryKind {
+        let i = Self::ALL.iter().position(|kind| kind == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+impl std::str::FromStr for EntryKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let kinds: Vec<&str> = Self::ALL.iter().map(|kind| kind.as_str()).collect();
+                format!("Unknown entry kind '{}' (expected one of: {})", s, kinds.join(", "))
+            })
+    }
+}
+
+// digits of a card number typed with or without spaces and dashes, checked with Luhn:
+fn check_card_number(value: &str) -> Result<(), String> {
+    let digits: Vec<u32> = value
+        .chars()
+        .filter(|c| *c != ' ' && *c != '-')
+        .map(|c| c.to_digit(10).ok_or("may only contain digits"))
+        .collect::<Result<_, _>>()?;
+    if !(12..=19).contains(&digits.len()) {
+        return Err("must have 12 to 19 digits".into());
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2, d * 2) {
+            (0, _) => d,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    if !sum.is_multiple_of(10) {
+        return Err("is not a valid card number".into());
+    }
+    Ok(())
+}
+
+// `MM/YY` or `MM/YYYY`:
+fn check_expiry(value: &str) -> Result<(), String> {
+    let valid = value.split_once('/').is_some_and(|(month, year)| {
+        month.len() == 2
+            && month.parse::<u32>().is_ok_and(|m| (1..=12).contains(&m))
+            && (year.len() == 2 || year.len() == 4)
+            && year.chars().all(|c| c.is_ascii_digit())
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err("must look like MM/YY".into())
+    }
+}
+
+fn check_cvv(value: &str) -> Result<(), String> {
+    if (3..=4).contains(&value.len()) && value.chars().all(|c| c.is_ascii_digit()) {
+        Ok(())
+    } else {
+        Err("must be 3 or 4 digits".into())
+    }
+}
+
+fn check_private_key(value: &str) -> Result<(), String> {
+    if value.contains("PRIVATE KEY") {
+        Ok(())
+    } else {
+        Err("is not a PEM or OpenSSH private key".into())
+    }
+}
+
+fn check_public_key(value: &str) -> Result<(), String> {
+    let algorithm = value.split_whitespace().next().unwrap_or("");
+    if ["ssh-", "ecdsa-", "sk-"].iter().any(|prefix| algorithm.starts_with(prefix)) {
+        Ok(())
+    } else {
+        Err("is not an OpenSSH public key".into())
+    }
+}
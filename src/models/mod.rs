@@ -1,2 +1,4 @@
+pub mod kinds;
+pub mod secret;
 pub mod structs;
 pub mod types;
@@ -0,0 +1,193 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::ops::Deref;
+use zeroize::Zeroize;
+
+use crate::utils::hardening::{lock_memory, unlock_memory};
+
+// Room reserved up front so typing into a buffer rarely has to reallocate
+const SECRET_STRING_CAPACITY: usize = 64;
+
+/// A string holding a secret (master password, entry password, input buffer).
+///
+/// The buffer is locked into RAM, zeroed when the value is dropped and when it has
+/// to grow, and `Debug` never prints it.
+#[derive(Default, PartialEq, Eq)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new() -> Self {
+        Self::from(String::with_capacity(SECRET_STRING_CAPACITY))
+    }
+
+    fn lock(&self) {
+        lock_memory(self.0.as_ptr(), self.0.capacity());
+    }
+
+    fn unlock(&self) {
+        unlock_memory(self.0.as_ptr(), self.0.capacity());
+    }
+
+    // the secret itself, for the few places that need it:
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    // append a character without leaving a copy behind in a freed buffer:
+    pub fn push(&mut self, c: char) {
+        if self.0.len() + c.len_utf8() > self.0.capacity() {
+            let capacity = (self.0.capacity() * 2).max(SECRET_STRING_CAPACITY);
+            let mut grown = String::with_capacity(capacity);
+            lock_memory(grown.as_ptr(), grown.capacity());
+            grown.push_str(&self.0);
+            self.0.zeroize();
+            self.unlock();
+            self.0 = grown;
+        }
+        self.0.push(c);
+    }
+
+    pub fn pop(&mut self) -> Option<char> {
+        let c = self.0.pop()?;
+        // Wipe the bytes left past the new end; only spare capacity is touched, so the
+        // string stays valid UTF-8
+        let spare = unsafe { self.0.as_mut_vec() }.spare_capacity_mut();
+        for byte in spare.iter_mut().take(c.len_utf8()) {
+            byte.write(0);
+        }
+        Some(c)
+    }
+
+    pub fn clear(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Clone for SecretString {
+    fn clone(&self) -> Self {
+        let mut copy = Self::from(String::with_capacity(
+            self.0.capacity().max(SECRET_STRING_CAPACITY),
+        ));
+        copy.0.push_str(&self.0);
+        copy
+    }
+}
+
+// Takes ownership, so the secret is not copied
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        let secret = Self(value);
+        secret.lock();
+        secret
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        let mut secret = Self::from(String::with_capacity(
+            value.len().max(SECRET_STRING_CAPACITY),
+        ));
+        secret.0.push_str(value);
+        secret
+    }
+}
+
+impl Deref for SecretString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+        self.unlock();
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString([REDACTED])")
+    }
+}
+
+impl Serialize for SecretString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::from)
+    }
+}
+
+/// A 256-bit key (vault data key, derived key) kept on its own locked heap allocation,
+/// zeroed on drop and never printed.
+#[derive(PartialEq, Eq)]
+pub struct SecretKey(Box<[u8; 32]>);
+
+impl SecretKey {
+    // take over a key, wiping the caller's copy:
+    pub fn new(bytes: &mut [u8; 32]) -> Self {
+        let mut key = Box::new([0u8; 32]);
+        lock_memory(key.as_ptr(), key.len());
+        key.copy_from_slice(bytes);
+        bytes.zeroize();
+        Self(key)
+    }
+
+    pub fn expose(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl Clone for SecretKey {
+    fn clone(&self) -> Self {
+        let mut copy = *self.0;
+        Self::new(&mut copy)
+    }
+}
+
+impl Default for SecretKey {
+    fn default() -> Self {
+        Self::new(&mut [0u8; 32])
+    }
+}
+
+// Take over a decrypted key, wiping the buffer it came in
+impl TryFrom<Vec<u8>> for SecretKey {
+    type Error = String;
+
+    fn try_from(mut bytes: Vec<u8>) -> Result<Self, String> {
+        let key = <[u8; 32]>::try_from(bytes.as_slice())
+            .map(|mut key| Self::new(&mut key))
+            .map_err(|_| format!("Invalid key length {}", bytes.len()));
+        bytes.zeroize();
+        key
+    }
+}
+
+impl Deref for SecretKey {
+    type Target = [u8; 32];
+
+    fn deref(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+        unlock_memory(self.0.as_ptr(), self.0.len());
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretKey([REDACTED])")
+    }
+}
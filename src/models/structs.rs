@@ -1,28 +1,531 @@
 use serde::{Deserialize, Serialize};
+use serde_with::{base64::Base64, serde_as};
 
+use std::collections::BTreeMap;
+
+use crate::models::kinds::EntryKind;
+use crate::models::secret::SecretString;
+
+/// Newest vault file format this build can read and the one it writes.
+pub const VAULT_FORMAT_VERSION: u32 = 4;
+
+/// First format whose header is authenticated as associated data of the entries.
+pub const AUTHENTICATED_HEADER_VERSION: u32 = 4;
+
+/// Version assumed for vault files written before the header existed.
+pub const LEGACY_VAULT_FORMAT_VERSION: u32 = 1;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Metadata {
     pub url: Option<String>,
     pub notes: Option<String>,
+    /// Extra values such as API keys or security questions, in display order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<CustomField>,
 }
 impl Default for Metadata {
     fn default() -> Self {
-        Self { url: None, notes: None }
+        Self { url: None, notes: None, fields: Vec::new() }
+    }
+}
+
+/// What a custom field holds, which decides how it is checked and shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FieldKind {
+    #[default]
+    #[serde(rename = "text")]
+    Text,
+    /// Masked like a password, e.g. a PIN or an API key.
+    #[serde(rename = "hidden")]
+    Hidden,
+    #[serde(rename = "url")]
+    Url,
+    #[serde(rename = "email")]
+    Email,
+    /// Base32 TOTP secret.
+    #[serde(rename = "totp")]
+    Totp,
+    /// Calendar date as `YYYY-MM-DD`.
+    #[serde(rename = "date")]
+    Date,
+}
+
+impl FieldKind {
+    pub const ALL: [FieldKind; 6] = [
+        FieldKind::Text,
+        FieldKind::Hidden,
+        FieldKind::Url,
+        FieldKind::Email,
+        FieldKind::Totp,
+        FieldKind::Date,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FieldKind::Text => "text",
+            FieldKind::Hidden => "hidden",
+            FieldKind::Url => "url",
+            FieldKind::Email => "email",
+            FieldKind::Totp => "totp",
+            FieldKind::Date => "date",
+        }
+    }
+
+    // values that are masked unless explicitly asked for:
+    pub fn is_secret(&self) -> bool {
+        matches!(self, FieldKind::Hidden | FieldKind::Totp)
+    }
+
+    // the kind after this one, for cycling through them:
+    pub fn next(&self) -> FieldKind {
+        let i = Self::ALL.iter().position(|kind| kind == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    // check that a value fits the kind:
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        let valid = match self {
+            FieldKind::Text | FieldKind::Hidden => true,
+            FieldKind::Url => value
+                .split_once("://")
+                .is_some_and(|(scheme, rest)| !scheme.is_empty() && !rest.is_empty()),
+            FieldKind::Email => {
+                !value.contains(char::is_whitespace)
+                    && value
+                        .split_once('@')
+                        .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.'))
+            }
+            FieldKind::Totp => {
+                let seed: Vec<char> = value
+                    .chars()
+                    .filter(|c| !c.is_whitespace() && *c != '=')
+                    .collect();
+                !seed.is_empty()
+                    && seed
+                        .iter()
+                        .all(|c| c.is_ascii_alphabetic() || ('2'..='7').contains(c))
+            }
+            FieldKind::Date => chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+        };
+        if valid {
+            Ok(())
+        } else {
+            // The value is left out, as it may be a secret
+            Err(format!("is not a valid {} value", self.as_str()))
+        }
+    }
+}
+
+/// A named, typed value on an entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomField {
+    pub name: String,
+    #[serde(default)]
+    pub kind: FieldKind,
+    /// Wiped from memory like the password, whatever the kind.
+    pub value: SecretString,
+}
+
+/// When an entry was created, changed and used, as RFC 3339 times in UTC. Each is empty
+/// when unknown, e.g. for entries saved before they were tracked.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Timestamps {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub created: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub modified: String,
+    /// Last change of the password, or of the main secret of other kinds.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub password_changed: String,
+    /// Last time the secret was copied or printed.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub last_used: String,
+    /// When the entry was moved to the trash; empty for live entries.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub deleted: String,
+}
+
+impl Timestamps {
+    // Second precision in UTC, so the strings sort in time order
+    pub fn now() -> String {
+        chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    }
+
+    // local time for display, `never` when unknown:
+    pub fn display(value: &str) -> String {
+        chrono::DateTime::parse_from_rfc3339(value)
+            .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|_| "never".to_string())
+    }
+
+    // whole days since `value`, if it is set:
+    pub fn days_since(value: &str) -> Option<i64> {
+        let time = chrono::DateTime::parse_from_rfc3339(value).ok()?;
+        Some((chrono::Utc::now() - time.with_timezone(&chrono::Utc)).num_days())
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PasswordEntry {
     pub name: String,
+    /// Internal UUID, fixed when the entry is created. Older vaults kept the login
+    /// here; see `migrate_entry_ids`.
     pub id: String,
-    pub password: String,
+    #[serde(default)]
+    pub kind: EntryKind,
+    #[serde(default)]
+    pub username: String,
+    pub password: SecretString,
+    /// Folder path such as `work/aws/prod`; empty for entries at the top level.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub folder: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Values of the fields `kind` asks for, by `DetailField::key`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: BTreeMap<String, SecretString>,
     pub metadata: Metadata,
+    #[serde(default)]
+    pub times: Timestamps,
+    /// Earlier passwords (or main secrets of other kinds), newest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<PasswordHistoryEntry>,
+}
+
+/// A password an entry used to have.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasswordHistoryEntry {
+    pub value: SecretString,
+    /// When it was replaced, as an RFC 3339 time.
+    pub replaced: String,
+}
+
+impl PasswordEntry {
+    // new entry with a fresh id:
+    pub fn new(name: String, username: String, password: SecretString) -> Self {
+        let now = Timestamps::now();
+        Self {
+            name,
+            id: uuid::Uuid::new_v4().to_string(),
+            kind: EntryKind::Login,
+            username,
+            password,
+            folder: String::new(),
+            tags: Vec::new(),
+            details: BTreeMap::new(),
+            metadata: Metadata::default(),
+            times: Timestamps {
+                created: now.clone(),
+                modified: now.clone(),
+                password_changed: now,
+                last_used: String::new(),
+                deleted: String::new(),
+            },
+            history: Vec::new(),
+        }
+    }
+
+    // Make this the edited version of `original`: creation and last use carry over, the
+    // modification time moves, and if the secret changed the old one goes into the
+    // history, which keeps at most `history_limit` values
+    pub fn replace(&mut self, original: &PasswordEntry, history_limit: usize) {
+        let now = Timestamps::now();
+        let secret_changed = self.primary_secret() != original.primary_secret();
+        self.times = Timestamps {
+            created: original.times.created.clone(),
+            modified: now.clone(),
+            password_changed: if secret_changed {
+                now.clone()
+            } else {
+                original.times.password_changed.clone()
+            },
+            last_used: original.times.last_used.clone(),
+            deleted: original.times.deleted.clone(),
+        };
+        self.history = original.history.clone();
+        if let (true, Some(old)) = (secret_changed, original.primary_secret()) {
+            self.history.insert(0, PasswordHistoryEntry {
+                value: old.into(),
+                replaced: now,
+            });
+        }
+        self.history.truncate(history_limit);
+    }
+
+    // Set the password, or the value `primary_secret` reads for other kinds
+    pub fn set_primary_secret(&mut self, value: SecretString) {
+        match (self.kind, self.kind.primary_field()) {
+            (_, Some(key)) => {
+                self.details.insert(key.to_string(), value);
+            }
+            (EntryKind::Login, None) => self.password = value,
+            (EntryKind::SecureNote, None) => self.metadata.notes = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    // Bring back the value at `index` of the history; the current one takes its place
+    // at the top of the history
+    pub fn restore_from_history(&mut self, index: usize, history_limit: usize) -> Result<(), String> {
+        let value = self
+            .history
+            .get(index)
+            .ok_or_else(|| format!("{} has no password #{} in its history", self.name, index + 1))?
+            .value
+            .clone();
+        let mut original = self.clone();
+        original.history.remove(index);
+        self.set_primary_secret(value);
+        self.replace(&original, history_limit);
+        Ok(())
+    }
+
+    // whether the entry sits in the trash:
+    pub fn is_trashed(&self) -> bool {
+        !self.times.deleted.is_empty()
+    }
+
+    // days since the password (or main secret) last changed, if known:
+    pub fn password_age_days(&self) -> Option<i64> {
+        let changed = match self.times.password_changed.as_str() {
+            "" => &self.times.created,
+            changed => changed,
+        };
+        Timestamps::days_since(changed)
+    }
+
+    // Whether the secret is older than `max_age_days` and due for rotation; entries
+    // without a secret or a known age never are, and 0 turns the check off
+    pub fn is_password_stale(&self, max_age_days: u64) -> bool {
+        max_age_days > 0
+            && self.primary_secret().is_some()
+            && self.password_age_days().is_some_and(|days| days > max_age_days as i64)
+    }
+
+    // value of one of the kind's fields, empty when not set:
+    pub fn detail(&self, key: &str) -> &str {
+        self.details.get(key).map_or("", |value| value.expose())
+    }
+
+    // What "copy" and `rsp get` without `--field` mean for this kind: the password
+    // of a login, the text of a note, the number of a card and so on
+    pub fn primary_secret(&self) -> Option<&str> {
+        match (self.kind, self.kind.primary_field()) {
+            (_, Some(key)) => Some(self.detail(key)),
+            (EntryKind::Login, None) => Some(self.password.expose()),
+            (EntryKind::SecureNote, None) => self.metadata.notes.as_deref(),
+            _ => None,
+        }
+        .filter(|value| !value.is_empty())
+    }
+
+    // check the fields the kind requires and the format of every typed value:
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Name cannot be empty".into());
+        }
+        for field in self.kind.fields() {
+            let value = self.detail(field.key);
+            if value.is_empty() {
+                if field.required {
+                    return Err(format!("{} is required for a {}", field.label, self.kind.label()));
+                }
+                continue;
+            }
+            field.kind.validate(value)
+                .and_then(|_| field.check.map_or(Ok(()), |check| check(value)))
+                .map_err(|e| format!("{} {}", field.label, e))?;
+        }
+        for field in &self.metadata.fields {
+            field.kind.validate(&field.value)
+                .map_err(|e| format!("Field '{}' {}", field.name, e))?;
+        }
+        Ok(())
+    }
+
+    // Value of a built-in field (password, username, url, notes), of one of the kind's
+    // fields (by key or label) or of a custom field, matched regardless of case
+    pub fn field_value(&self, field: &str) -> Option<&str> {
+        match field.to_lowercase().as_str() {
+            "password" => Some(self.password.expose()),
+            "username" => Some(&self.username),
+            "url" => self.metadata.url.as_deref(),
+            "notes" => self.metadata.notes.as_deref(),
+            _ => self
+                .kind
+                .fields()
+                .iter()
+                .find(|detail| detail.key.eq_ignore_ascii_case(field) || detail.label.eq_ignore_ascii_case(field))
+                .map(|detail| self.detail(detail.key))
+                .or_else(|| {
+                    self.metadata
+                        .fields
+                        .iter()
+                        .find(|custom| custom.name.eq_ignore_ascii_case(field))
+                        .map(|custom| custom.value.expose())
+                }),
+        }
+    }
+}
+
+/// Argon2 variant used to derive the vault key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KdfAlgorithm {
+    #[serde(rename = "argon2id")]
+    Argon2id,
+    #[serde(rename = "argon2i")]
+    Argon2i,
+    #[serde(rename = "argon2d")]
+    Argon2d,
+}
+
+/// How a master password is turned into Argon2 input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KdfVersion {
+    /// Password pre-hashed with `fibbil_hash`, which folds every character into ASCII.
+    #[serde(rename = "v1")]
+    V1,
+    /// NFC-normalized UTF-8 password fed to Argon2 as is.
+    #[serde(rename = "v2")]
+    V2,
+}
+
+/// Key derivation settings stored in the vault header.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Missing in vaults written before it was recorded, which all used v1.
+    #[serde(default = "legacy_kdf_version")]
+    pub version: KdfVersion,
+    pub algorithm: KdfAlgorithm,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            version: KdfVersion::V2,
+            algorithm: KdfAlgorithm::Argon2id,
+            memory_kib: argon2::Params::DEFAULT_M_COST,
+            iterations: argon2::Params::DEFAULT_T_COST,
+            parallelism: argon2::Params::DEFAULT_P_COST,
+        }
+    }
+}
+impl KdfParams {
+    // settings of vaults that predate the header:
+    pub fn legacy() -> Self {
+        Self {
+            version: KdfVersion::V1,
+            ..Self::default()
+        }
+    }
+}
+
+fn legacy_kdf_version() -> KdfVersion {
+    KdfVersion::V1
+}
+
+/// AEAD cipher protecting the vault payload.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CipherKind {
+    #[default]
+    #[serde(rename = "aes-256-gcm")]
+    Aes256Gcm,
+    /// 24-byte random nonces, for vaults saved very often.
+    #[serde(rename = "xchacha20-poly1305")]
+    XChaCha20Poly1305,
+}
+
+/// The kind of secret a key slot is unlocked with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySlotKind {
+    Password,
+    KeyFile,
+    RecoveryCode,
+}
+
+/// A copy of the vault data key, wrapped with a key derived from one unlock method.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeySlot {
+    pub id: String,
+    pub kind: KeySlotKind,
+    /// Password slots only: a key file is mixed into the password as a second factor.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub key_file: bool,
+    pub label: String,
+    pub created: String,
+    pub kdf: KdfParams,
+    #[serde_as(as = "Base64")]
+    pub salt: Vec<u8>,
+    #[serde_as(as = "Base64")]
+    pub nonce: Vec<u8>,
+    #[serde_as(as = "Base64")]
+    pub wrapped_key: Vec<u8>,
+}
+
+/// Vault metadata stored next to the encrypted entries.
+///
+/// From format 3 on, the entries are encrypted with a random data key that is
+/// wrapped once per unlock method in `key_slots`. Older vaults have no slots and
+/// derive the payload key directly from the master password with `salt`/`kdf`.
+/// From format 4 on, the header (all but `generation`) is bound to the entries as
+/// AEAD associated data, so any change to it makes decryption fail.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultHeader {
+    #[serde(default = "legacy_format_version")]
+    pub version: u32,
+    #[serde(default)]
+    pub cipher: CipherKind,
+    /// Incremented on every save; a save based on an older generation is refused.
+    #[serde(default)]
+    pub generation: u64,
+    /// Incremented on every save and, unlike `generation`, authenticated with the
+    /// entries, so an older copy of the vault cannot pass itself off as newer.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub revision: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub key_slots: Vec<KeySlot>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kdf: Option<KdfParams>,
+    #[serde_as(as = "Option<Base64>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<Vec<u8>>,
+}
+impl VaultHeader {
+    pub fn new(key_slots: Vec<KeySlot>) -> Self {
+        Self {
+            version: VAULT_FORMAT_VERSION,
+            cipher: CipherKind::default(),
+            generation: 0,
+            revision: 0,
+            key_slots,
+            kdf: None,
+            salt: None,
+        }
+    }
+
+    // older vaults encrypt the payload with the password-derived key directly:
+    pub fn is_legacy(&self) -> bool {
+        self.key_slots.is_empty()
+    }
+}
+
+fn legacy_format_version() -> u32 {
+    LEGACY_VAULT_FORMAT_VERSION
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct EncryptedFile {
-    pub salt: String,
+    #[serde(flatten)]
+    pub header: VaultHeader,
     pub nonce: String,
     pub encrypted_data: String,
-}
\ No newline at end of file
+}
@@ -1,15 +1,16 @@
-use crate::models::structs::PasswordEntry;
+use crate::models::secret::SecretKey;
+use crate::models::structs::{PasswordEntry, VaultHeader};
 
 /// A vector of password entries loaded from the file.
 pub type PasswordEntries = Vec<PasswordEntry>;
 
-/// The encryption key derived from the master password.
-pub type EncryptionKey = [u8; 32];
+/// The key that encrypts the vault entries; zeroed when dropped.
+pub type EncryptionKey = SecretKey;
 
 /// A salt used in Argon2 key derivation.
 pub type Salt = Vec<u8>;
 
-/// Nonce used in AES-GCM encryption (12 bytes).
+/// Nonce stored next to a ciphertext (12 bytes for AES-GCM, 24 for XChaCha20-Poly1305).
 pub type Nonce = Vec<u8>;
 
 /// Ciphertext after encryption.
@@ -18,8 +19,8 @@ pub type CipherText = Vec<u8>;
 /// The result when loading or creating a password file:
 /// - Password entries
 /// - The derived encryption key
-/// - The salt used for key derivation
-pub type PasswordDataPayload = (PasswordEntries, EncryptionKey, Salt);
+/// - The vault header (salt and KDF parameters used for key derivation)
+pub type PasswordDataPayload = (PasswordEntries, EncryptionKey, VaultHeader);
 
 /// General success/error type when working with password files.
 pub type PasswordDataResult = Result<PasswordDataPayload, String>;
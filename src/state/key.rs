@@ -1,66 +1,106 @@
-use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit};
-use base64::{engine::general_purpose::STANDARD, Engine as _};
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine as _,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::fs::{self, File};
-use std::io::{self, Read, Write};
-use std::os::unix::fs::PermissionsExt;
+use std::fs;
+use std::io;
 use std::path::PathBuf;
+use zeroize::Zeroize;
 
-use crate::models::types::{DecryptedDataResult, EncryptedDataResult};
+use crate::config::settings::SETTINGS;
+use crate::data::vault::{get_data_dir, get_vault_state_dir};
+use crate::encryption::{decrypt::decrypt_data, encrypt::encrypt_data};
+use crate::models::types::EncryptionKey;
+use crate::utils::atomic_file::write_atomic;
+
+/// Environment variable holding the session token printed by `rsp unlock`.
+pub const SESSION_ENV_VAR: &str = "RSP_SESSION";
 
 // Encrypted key file format
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Serialize, Deserialize)]
 struct EncryptedKeyFile {
     nonce: String,
     encrypted_data: String,
 }
 
-// Get key file path
+// What the session token protects
+#[derive(Serialize, Deserialize)]
+struct SessionPayload {
+    key: Vec<u8>,
+    // Unix time after which the session is refused (0 = never)
+    expires: i64,
+}
+
+// Get key file path (one per vault)
 pub fn get_key_file_path() -> PathBuf {
-    let base_dir = dirs::data_dir()
-        .unwrap_or_else(|| PathBuf::from("/tmp"))
-        .join("rustpass");
-    fs::create_dir_all(&base_dir).expect("Failed to create key directory");
-    base_dir.join("key.enc")
+    get_vault_state_dir().join("key.enc")
 }
 
-// Derive fixed key for encryption (same as state.enc)
-fn derive_fixed_key() -> [u8; 32] {
-    let mut hasher = Sha256::default();
-    hasher.update("rustpass_state_key_v1");
-    let result = hasher.finalize();
-    let mut key = [0u8; 32];
-    key.copy_from_slice(&result);
-    key
+// Files older builds kept directly in the data dir: key.enc held the master password
+// and state.enc the unlock flag, both encrypted with a key built into the binary
+pub fn legacy_session_file_paths() -> [PathBuf; 2] {
+    let data_dir = get_data_dir();
+    [data_dir.join("key.enc"), data_dir.join("state.enc")]
 }
 
-// Encrypt data
-fn encrypt_data(data: &[u8]) -> EncryptedDataResult {
-    let key = derive_fixed_key();
-    let cipher = Aes256Gcm::new(&key.into());
-    let mut nonce = [0u8; 12];
-    for byte in &mut nonce {
-        *byte = rand::random();
+// Delete the files older builds left behind; anyone with the binary can decrypt them
+pub fn remove_legacy_session_files() -> io::Result<()> {
+    for path in legacy_session_file_paths() {
+        match fs::remove_file(&path) {
+            Ok(()) => log::info!("Removed legacy session file {:?}", path),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(io::Error::new(
+                    e.kind(),
+                    format!("Failed to delete legacy session file {}: {}", path.display(), e),
+                ))
+            }
+        }
     }
-    let ciphertext = cipher
-        .encrypt(&nonce.into(), data)
-        .map_err(|e| format!("Encryption failed: {}", e))?;
-    Ok((nonce.to_vec(), ciphertext))
+    Ok(())
 }
 
-// Decrypt data
-fn decrypt_data(ciphertext: &[u8], nonce: &[u8]) -> DecryptedDataResult {
-    let key = derive_fixed_key();
-    let cipher = Aes256Gcm::new(&key.into());
-    cipher
-        .decrypt(nonce.into(), ciphertext)
-        .map_err(|e| format!("Decryption failed: {}", e))
+// Derive the key wrapping the vault key from a session token. The token is 256 random
+// bits that only ever live in the user's environment, so a plain hash is enough.
+fn derive_session_key(token: &str) -> io::Result<[u8; 32]> {
+    let token = URL_SAFE_NO_PAD
+        .decode(token.trim())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Malformed session token"))?;
+    let mut hasher = Sha256::default();
+    hasher.update(b"rustpass_session_v1");
+    hasher.update(&token);
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&hasher.finalize());
+    Ok(key)
 }
 
-// Save master password to key file
-pub fn save_key(password: &str) -> io::Result<()> {
-    let data = password.as_bytes();
-    let (nonce, encrypted_data) = encrypt_data(data)
+// Start a session: wrap the vault data key with a fresh random token and return the
+// token. Only the wrapped key is written to disk; the token is handed to the user.
+pub fn save_key(key: &EncryptionKey) -> io::Result<String> {
+    let mut token = [0u8; 32];
+    rand::rng().fill_bytes(&mut token);
+    let token = URL_SAFE_NO_PAD.encode(token);
+
+    let expires = match SETTINGS.session_timeout_secs {
+        0 => 0,
+        secs => chrono::Utc::now().timestamp() + secs as i64,
+    };
+    let mut payload = SessionPayload {
+        key: key.to_vec(),
+        expires,
+    };
+    let data = serde_json::to_vec(&payload);
+    payload.key.zeroize();
+    let mut data = data.map_err(|e| {
+        io::Error::new(io::ErrorKind::Other, format!("Serialization failed: {}", e))
+    })?;
+
+    let encrypted = encrypt_data(&data, &derive_session_key(&token)?);
+    data.zeroize();
+    let (nonce, encrypted_data) = encrypted
         .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Encryption failed: {}", e)))?;
 
     let encrypted_file = EncryptedKeyFile {
@@ -68,51 +108,33 @@ pub fn save_key(password: &str) -> io::Result<()> {
         encrypted_data: STANDARD.encode(&encrypted_data),
     };
 
-    let path = get_key_file_path();
-    let mut file = File::create(&path).map_err(|e| {
+    // Written with 600 permissions
+    write_atomic(
+        &get_key_file_path(),
+        serde_json::to_string(&encrypted_file).unwrap().as_bytes(),
+    )?;
+
+    Ok(token)
+}
+
+// Load the vault data key with the session token from `RSP_SESSION`
+pub fn load_key() -> io::Result<EncryptionKey> {
+    let token = std::env::var(SESSION_ENV_VAR).map_err(|_| {
         io::Error::new(
-            io::ErrorKind::Other,
-            format!("Failed to create file: {}", e),
+            io::ErrorKind::NotFound,
+            format!("No session found ({} is not set)", SESSION_ENV_VAR),
         )
     })?;
-    file.write_all(serde_json::to_string(&encrypted_file).unwrap().as_bytes())
-        .map_err(|e| {
-            io::Error::new(io::ErrorKind::Other, format!("Failed to write file: {}", e))
-        })?;
 
-    // Set file permissions to 600 (owner read/write only)
-    #[cfg(unix)]
-    {
-        let perms = fs::Permissions::from_mode(0o600);
-        fs::set_permissions(&path, perms).map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                format!("Failed to set permissions: {}", e),
-            )
-        })?;
-    }
-
-    Ok(())
-}
-
-// Load master password from key file
-pub fn load_key() -> io::Result<String> {
     let path = get_key_file_path();
     if !path.exists() {
         return Err(io::Error::new(
             io::ErrorKind::NotFound,
-            "Key file not found",
+            "Session has ended",
         ));
     }
 
-    let mut file = File::open(&path).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::NotFound,
-            format!("Failed to open file: {}", e),
-        )
-    })?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).map_err(|e| {
+    let contents = fs::read_to_string(&path).map_err(|e| {
         io::Error::new(
             io::ErrorKind::InvalidData,
             format!("Failed to read file: {}", e),
@@ -137,18 +159,31 @@ pub fn load_key() -> io::Result<String> {
         )
     })?;
 
-    let decrypted = decrypt_data(&encrypted_data, &nonce).map_err(|e| {
+    let mut decrypted = decrypt_data(&encrypted_data, &derive_session_key(&token)?, &nonce)
+        .map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{} does not belong to the current session", SESSION_ENV_VAR),
+            )
+        })?;
+
+    let payload: Result<SessionPayload, _> = serde_json::from_slice(&decrypted);
+    decrypted.zeroize();
+    let mut payload = payload.map_err(|e| {
         io::Error::new(
             io::ErrorKind::InvalidData,
-            format!("Decryption failed: {}", e),
+            format!("Invalid session: {}", e),
         )
     })?;
 
-    String::from_utf8(decrypted).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!("Failed to parse password: {}", e),
-        )
+    if payload.expires != 0 && chrono::Utc::now().timestamp() > payload.expires {
+        payload.key.zeroize();
+        delete_key()?;
+        return Err(io::Error::new(io::ErrorKind::TimedOut, "Session has expired"));
+    }
+
+    EncryptionKey::try_from(payload.key).map_err(|_| {
+        io::Error::new(io::ErrorKind::InvalidData, "Invalid key length in key file")
     })
 }
 
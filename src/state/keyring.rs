@@ -0,0 +1,150 @@
+use std::ffi::CString;
+use std::io;
+
+use crate::data::vault::active_vault_name;
+use crate::models::types::EncryptionKey;
+
+// Key type for opaque data held by the kernel
+const KEY_TYPE: &str = "user";
+
+// Possessor: everything; same uid: view, read, search (so other shells of the user can
+// find the key in the user keyring without possessing it)
+const KEY_PERM: u32 = 0x3f0b_0000;
+
+/// Kernel keyring the vault key is kept in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyring {
+    /// Shared by all processes of the user until the timeout or logout.
+    User,
+    /// Scoped to the login session.
+    Session,
+}
+
+impl Keyring {
+    fn id(self) -> libc::c_long {
+        match self {
+            Keyring::User => libc::KEY_SPEC_USER_KEYRING as libc::c_long,
+            Keyring::Session => libc::KEY_SPEC_SESSION_KEYRING as libc::c_long,
+        }
+    }
+}
+
+// One key per vault
+fn key_description() -> CString {
+    CString::new(format!("rustpass:{}", active_vault_name())).expect("vault names contain no NUL")
+}
+
+fn keyctl(
+    operation: u32,
+    arg2: libc::c_long,
+    arg3: libc::c_long,
+    arg4: libc::c_long,
+    arg5: libc::c_long,
+) -> io::Result<libc::c_long> {
+    let ret = unsafe {
+        libc::syscall(libc::SYS_keyctl, operation as libc::c_long, arg2, arg3, arg4, arg5)
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(ret)
+}
+
+// find the vault key's serial number, if it is still in the keyring:
+fn find_key(keyring: Keyring) -> io::Result<Option<libc::c_long>> {
+    let key_type = CString::new(KEY_TYPE).unwrap();
+    let description = key_description();
+    match keyctl(
+        libc::KEYCTL_SEARCH,
+        keyring.id(),
+        key_type.as_ptr() as libc::c_long,
+        description.as_ptr() as libc::c_long,
+        0,
+    ) {
+        Ok(serial) => Ok(Some(serial)),
+        // Never added, expired or revoked
+        Err(e) if matches!(
+            e.raw_os_error(),
+            Some(libc::ENOKEY) | Some(libc::EKEYEXPIRED) | Some(libc::EKEYREVOKED)
+        ) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// put the vault key in the keyring; the kernel drops it after `timeout_secs` (0 = never):
+pub fn store_key(keyring: Keyring, key: &EncryptionKey, timeout_secs: u64) -> io::Result<()> {
+    let key_type = CString::new(KEY_TYPE).unwrap();
+    let description = key_description();
+
+    // add_key replaces an existing key with the same description
+    let serial = unsafe {
+        libc::syscall(
+            libc::SYS_add_key,
+            key_type.as_ptr(),
+            description.as_ptr(),
+            key.as_ptr(),
+            key.len(),
+            keyring.id(),
+        )
+    };
+    if serial < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let setup = keyctl(libc::KEYCTL_SETPERM, serial, KEY_PERM as libc::c_long, 0, 0).and_then(|_| {
+        if timeout_secs > 0 {
+            keyctl(libc::KEYCTL_SET_TIMEOUT, serial, timeout_secs as libc::c_long, 0, 0)?;
+        }
+        Ok(())
+    });
+    // Never leave a key behind with the default permissions or without its timeout
+    if let Err(e) = setup {
+        if let Err(revoke_error) = keyctl(libc::KEYCTL_REVOKE, serial, 0, 0, 0) {
+            log::error!("Failed to revoke half-stored session key: {}", revoke_error);
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+// read the vault key back; None once it expired or was revoked:
+pub fn load_key(keyring: Keyring) -> io::Result<Option<EncryptionKey>> {
+    let serial = match find_key(keyring)? {
+        Some(serial) => serial,
+        None => return Ok(None),
+    };
+
+    let mut key = [0u8; 32];
+    let len = match keyctl(
+        libc::KEYCTL_READ,
+        serial,
+        key.as_mut_ptr() as libc::c_long,
+        key.len() as libc::c_long,
+        0,
+    ) {
+        Ok(len) => len,
+        // It may expire between search and read
+        Err(e) if matches!(
+            e.raw_os_error(),
+            Some(libc::EKEYEXPIRED) | Some(libc::EKEYREVOKED)
+        ) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    // Wrap right away so the stack copy is wiped either way
+    let key = EncryptionKey::new(&mut key);
+    if len as usize != key.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Unexpected key length in keyring",
+        ));
+    }
+    Ok(Some(key))
+}
+
+// revoke the vault key so no process can read it any more:
+pub fn revoke_key(keyring: Keyring) -> io::Result<()> {
+    if let Some(serial) = find_key(keyring)? {
+        keyctl(libc::KEYCTL_REVOKE, serial, 0, 0, 0)?;
+    }
+    Ok(())
+}
@@ -1,18 +1,66 @@
-use crate::data::data::{load_passwords, save_passwords};
-use crate::models::structs::PasswordEntry;
-use crate::state::data::{load_state, save_state};
-use crate::state::key::{load_key, save_key, delete_key};
+use crate::daemon::client::DaemonClient;
+use crate::auth::keyfile::active_key_file;
+use crate::data::data::{load_passwords, load_vault_with_key};
+use crate::data::vault::get_vault_path;
+use crate::models::structs::{PasswordEntry, VaultHeader};
+use crate::state::data::{load_state, remove_state, save_state};
+use crate::config::settings::{SessionStore, SETTINGS};
+use crate::models::secret::SecretString;
+use crate::models::types::EncryptionKey;
+use crate::state::key::{
+    delete_key, load_key, remove_legacy_session_files, save_key, SESSION_ENV_VAR,
+};
+use crate::state::keyring::{self, Keyring};
 use std::io::{self, Write};
 use std::sync::{Arc, Mutex};
 
 use rpassword::read_password;
 
+// Clones are safe to hand out: the key and entry passwords wipe themselves on drop
 #[derive(Clone)]
 pub struct AppState {
     pub unlocked: bool,
     pub passwords: Vec<PasswordEntry>,
-    pub encryption_key: [u8; 32],
-    pub salt: Vec<u8>,
+    pub encryption_key: EncryptionKey,
+    pub header: VaultHeader,
+}
+
+// Kernel keyring holding the session key, if configured instead of the state files
+fn session_keyring() -> Option<Keyring> {
+    match SETTINGS.session_store {
+        SessionStore::File => None,
+        SessionStore::UserKeyring => Some(Keyring::User),
+        SessionStore::SessionKeyring => Some(Keyring::Session),
+    }
+}
+
+// Open the vault key of the current session
+fn load_session_key() -> io::Result<EncryptionKey> {
+    if let Some(keyring) = session_keyring() {
+        return keyring::load_key(keyring)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "App is locked"));
+    }
+
+    if !load_state()? {
+        return Err(io::Error::new(io::ErrorKind::Other, "App is locked"));
+    }
+    load_key().map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            format!("Failed to load key: {}. Please unlock again.", e),
+        )
+    })
+}
+
+// Forget the session key: revoke it in the keyring, or delete the state/key files
+fn end_session() -> io::Result<()> {
+    remove_legacy_session_files()?;
+    if let Some(keyring) = session_keyring() {
+        keyring::revoke_key(keyring)?;
+    } else {
+        save_state(false)?;
+    }
+    delete_key()
 }
 
 pub struct StateManager {
@@ -34,6 +82,22 @@ impl StateManager {
             }
         }
 
+        // The kernel drops the key once the session times out
+        if let Some(keyring) = session_keyring() {
+            return match keyring::load_key(keyring) {
+                Ok(key) => key.is_some(),
+                Err(e) => {
+                    log::warn!("Failed to read kernel keyring: {}. Assuming locked.", e);
+                    false
+                }
+            };
+        }
+
+        // Other processes can only join an unlocked vault through a session token
+        if std::env::var_os(SESSION_ENV_VAR).is_none() {
+            return false;
+        }
+
         // Then check the state file
         match load_state() {
             Ok(unlocked) => unlocked,
@@ -47,44 +111,43 @@ impl StateManager {
     pub fn unlock(
         &self,
         passwords: Vec<PasswordEntry>,
-        key: [u8; 32],
-        salt: Vec<u8>,
-        password_opt: Option<&str>,
-    ) -> io::Result<()> {
-        let password = match password_opt {
-            Some(p) => p.to_string(),
-            None => {
-                print!("Enter master password: ");
-                std::io::stdout().flush()?;
-                read_password()?
-            }
-        };
-
-        // Verify password by attempting to load passwords
-        let (loaded_passwords, loaded_key, loaded_salt) =
-            load_passwords(crate::PASSWORD_FILE_PATH, &password).map_err(|e| {
-                io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    format!("Invalid password: {}", e),
-                )
-            })?;
-
+        key: EncryptionKey,
+        header: VaultHeader,
+    ) -> io::Result<Option<String>> {
         let app_state = AppState {
             unlocked: true,
-            passwords: loaded_passwords,
-            encryption_key: loaded_key,
-            salt: loaded_salt,
+            passwords,
+            encryption_key: key,
+            header,
         };
 
+        // Whatever older builds cached is readable by anyone with the binary
+        remove_legacy_session_files()?;
+
         // Update local state
         match self.state.lock() {
             Ok(mut state) => {
+                let token = match session_keyring() {
+                    Some(keyring) => {
+                        keyring::store_key(
+                            keyring,
+                            &app_state.encryption_key,
+                            SETTINGS.session_timeout_secs,
+                        )?;
+                        // The keyring replaces the state files; don't leave old ones behind
+                        remove_state()?;
+                        None
+                    }
+                    None => {
+                        // Wrap the data key for a new session; the caller hands out the token
+                        let token = save_key(&app_state.encryption_key)?;
+                        // Update state file
+                        save_state(true)?;
+                        Some(token)
+                    }
+                };
                 *state = Some(app_state);
-                // Update state file
-                save_state(true)?;
-                // Save master password to key file
-                save_key(&password)?;
-                Ok(())
+                Ok(token)
             }
             Err(e) => Err(io::Error::new(io::ErrorKind::Other, e.to_string())),
         }
@@ -103,45 +166,35 @@ impl StateManager {
             }
         }
 
-        // Check state file
-        if load_state()? {
-            // State file indicates unlocked, use cached key
-            let password = load_key().map_err(|e| {
+        // Join the session started by `rsp unlock`
+        let key = load_session_key()?;
+
+        let (passwords, key, header) =
+            load_vault_with_key(&get_vault_path(), &key).map_err(|e| {
                 io::Error::new(
-                    io::ErrorKind::Other,
-                    format!("Failed to load key: {}. Please unlock again.", e),
+                    io::ErrorKind::InvalidInput,
+                    format!("Cached key no longer opens the vault: {}. Please unlock again.", e),
                 )
             })?;
 
-            let (passwords, key, salt) =
-                load_passwords(crate::PASSWORD_FILE_PATH, &password).map_err(|e| {
-                    io::Error::new(
-                        io::ErrorKind::InvalidInput,
-                        format!("Invalid password: {}", e),
-                    )
-                })?;
-
-            let app_state = AppState {
-                unlocked: true,
-                passwords,
-                encryption_key: key,
-                salt,
-            };
-            *state_guard = Some(app_state.clone());
-            Ok(app_state)
-        } else {
-            Err(io::Error::new(io::ErrorKind::Other, "App is locked"))
-        }
+        let app_state = AppState {
+            unlocked: true,
+            passwords,
+            encryption_key: key,
+            header,
+        };
+        *state_guard = Some(app_state.clone());
+        Ok(app_state)
     }
 
     pub fn lock(&self) -> io::Result<()> {
         // Prompt for password to verify
         print!("Enter master password to lock: ");
         std::io::stdout().flush()?;
-        let password = read_password()?;
+        let password = SecretString::from(read_password()?);
 
         // Verify password
-        let _ = load_passwords(crate::PASSWORD_FILE_PATH, &password).map_err(|e| {
+        let _ = load_passwords(&get_vault_path(), &password, active_key_file().as_deref()).map_err(|e| {
             io::Error::new(
                 io::ErrorKind::InvalidInput,
                 format!("Invalid password: {}", e),
@@ -152,16 +205,31 @@ impl StateManager {
         match self.state.lock() {
             Ok(mut state) => {
                 *state = None;
-                // Update state file
-                save_state(false)?;
-                // Delete key file
-                delete_key()?;
-                Ok(())
+                end_session()
             }
             Err(e) => Err(io::Error::new(io::ErrorKind::Other, e.to_string())),
         }
     }
 
+    // Drop every cached credential (in-memory state, state/key files and the daemon's unlock)
+    // without prompting, e.g. after the master password changed
+    pub fn invalidate(&self) -> io::Result<()> {
+        match self.state.lock() {
+            Ok(mut state) => {
+                *state = None;
+            }
+            Err(e) => {
+                return Err(io::Error::new(io::ErrorKind::Other, e.to_string()));
+            }
+        }
+        end_session()?;
+
+        if DaemonClient::is_running() {
+            DaemonClient::lock()?;
+        }
+        Ok(())
+    }
+
     pub fn ensure_unlocked(&self) -> io::Result<()> {
         if self.is_unlocked() {
             // Fetch state to ensure it's initialized
@@ -171,10 +239,10 @@ impl StateManager {
 
         print!("Enter master password: ");
         std::io::stdout().flush()?;
-        let password = read_password()?;
+        let password = SecretString::from(read_password()?);
 
-        let (passwords, key, salt) =
-            load_passwords(crate::PASSWORD_FILE_PATH, &password).map_err(|e| {
+        let (passwords, key, header) =
+            load_passwords(&get_vault_path(), &password, active_key_file().as_deref()).map_err(|e| {
                 io::Error::new(
                     io::ErrorKind::InvalidInput,
                     format!("Invalid password: {}", e),
@@ -185,7 +253,7 @@ impl StateManager {
             unlocked: true,
             passwords,
             encryption_key: key,
-            salt,
+            header,
         };
 
         match self.state.lock() {
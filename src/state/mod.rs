@@ -1,3 +1,4 @@
 pub mod data;
 pub mod key;
-pub mod manager;
\ No newline at end of file
+pub mod keyring;
+pub mod manager;
@@ -1,8 +1,17 @@
-use crate::data::data::save_passwords;
-use crate::models::structs::{ Metadata, PasswordEntry };
+use crate::auth::keyfile::active_key_file;
+use crate::config::settings::SETTINGS;
+use crate::data::data::{ change_master_password, record_use, update_passwords };
+use crate::data::folders::{ is_in_folder, list_folders, normalize_folder, parse_tags, rename_folder };
+use crate::data::trash::{ empty_trash, move_to_trash, purge_from_trash, restore_from_trash, split_trash };
+use crate::state::manager::STATE_MANAGER;
+use crate::models::kinds::EntryKind;
+use crate::models::secret::SecretString;
+use crate::models::structs::{ Metadata, PasswordEntry, VaultHeader };
+use crate::models::types::EncryptionKey;
 use crate::utils::fuzzy_finder::fuzzy_match;
+use crate::utils::generator::GeneratorOptions;
 use crate::utils::verify_passwords::verify_password;
-use crate::PASSWORD_FILE_PATH;
+use crate::data::vault::get_vault_path;
 // Import Notification from widgets (adjust the module path as needed)
 use crate::tui::layout::restore_terminal;
 use crate::tui::widgets::{ modal::Modal, modal::ModalType, notification::Notification };
@@ -10,33 +19,56 @@ use crate::tui::widgets::{ modal::Modal, modal::ModalType, notification::Notific
 use crossterm::event::{ KeyCode, KeyEvent, KeyModifiers };
 use log::debug;
 use ratatui::style::Color;
+use std::collections::HashSet;
 use std::time::Instant;
 use arboard::Clipboard;
 
-use super::widgets::modal::{ ConfirmationType, InputType };
+use super::widgets::modal::{ ConfirmationType, EntryField, InputType };
 
 // struct for the app:
 pub struct App {
     pub running: bool,
     pub search_input: String,
+    // live entries; the ones in the trash are kept apart, most recently deleted first
     pub all_passwords: Vec<PasswordEntry>,
+    pub trash: Vec<PasswordEntry>,
     pub filtered_passwords: Vec<PasswordEntry>,
     pub selected_index: usize,
     pub show_help: bool,
     pub multi_selected: Vec<String>,
     pub notification: Option<Notification>,
     pub modal: Option<Modal>,
-    pub encryption_key: [u8; 32], // Add this
-    pub salt: Vec<u8>, // Add this
+    pub encryption_key: EncryptionKey,
+    pub header: VaultHeader,
+    // folder tree: whether it has the cursor, which row, and which folders are folded
+    pub folder_focus: bool,
+    pub folder_index: usize,
+    pub collapsed_folders: HashSet<String>,
+    // entries outside this folder are hidden; empty for all entries
+    pub selected_folder: String,
+}
+
+// a visible row of the folder tree; the first one (empty path) stands for all entries:
+pub struct FolderRow {
+    pub path: String,
+    pub depth: usize,
+    pub has_children: bool,
+    pub collapsed: bool,
 }
 
 // implementation for the app:
 impl App {
-    pub fn new(passwords: Vec<PasswordEntry>, encryption_key: [u8; 32], salt: Vec<u8>) -> Self {
+    pub fn new(
+        passwords: Vec<PasswordEntry>,
+        encryption_key: EncryptionKey,
+        header: VaultHeader
+    ) -> Self {
+        let (passwords, trash) = split_trash(passwords);
         Self {
             running: true,
             search_input: String::new(),
             all_passwords: passwords.clone(),
+            trash,
             filtered_passwords: passwords,
             selected_index: 0,
             show_help: false,
@@ -44,10 +76,19 @@ impl App {
             notification: None,
             modal: None,
             encryption_key,
-            salt,
+            header,
+            folder_focus: false,
+            folder_index: 0,
+            collapsed_folders: HashSet::new(),
+            selected_folder: String::new(),
         }
     }
 
+    // take the entries of a freshly saved or loaded vault, setting the trashed ones apart:
+    pub fn set_passwords(&mut self, passwords: Vec<PasswordEntry>) {
+        (self.all_passwords, self.trash) = split_trash(passwords);
+    }
+
     // update the search input:
     pub fn update_search(&mut self, c: char) {
         // Prevent adding spaces at the beginning of the search string
@@ -64,16 +105,86 @@ impl App {
         let search = self.search_input.to_lowercase();
         self.filtered_passwords = self.all_passwords
             .iter()
+            .filter(|p| is_in_folder(&p.folder, &self.selected_folder))
             .filter(|p| {
                 let name = p.name.to_lowercase();
-                let id = p.id.to_lowercase();
-                fuzzy_match(&search, &name) || fuzzy_match(&search, &id)
+                let username = p.username.to_lowercase();
+                fuzzy_match(&search, &name) ||
+                    fuzzy_match(&search, &username) ||
+                    p.tags.iter().any(|tag| fuzzy_match(&search, &tag.to_lowercase()))
             })
             .cloned()
             .collect();
         self.selected_index = 0;
     }
 
+    // rows of the folder tree, leaving out the contents of folded folders:
+    pub fn folder_rows(&self) -> Vec<FolderRow> {
+        let folders = list_folders(&self.all_passwords);
+        let mut rows = vec![FolderRow {
+            path: String::new(),
+            depth: 0,
+            has_children: false,
+            collapsed: false,
+        }];
+        for (i, folder) in folders.iter().enumerate() {
+            let hidden = self.collapsed_folders
+                .iter()
+                .any(|collapsed| collapsed != folder && is_in_folder(folder, collapsed));
+            if hidden {
+                continue;
+            }
+            // Subfolders sort right after their parent
+            let has_children = folders
+                .get(i + 1)
+                .is_some_and(|next| next != folder && is_in_folder(next, folder));
+            rows.push(FolderRow {
+                path: folder.clone(),
+                depth: folder.matches('/').count() + 1,
+                has_children,
+                collapsed: self.collapsed_folders.contains(folder),
+            });
+        }
+        rows
+    }
+
+    // move the cursor in the folder tree and show the entries of that folder:
+    pub fn move_folder_selection(&mut self, down: bool) {
+        let rows = self.folder_rows();
+        let len = rows.len();
+        let current = self.folder_index.min(len - 1);
+        self.folder_index = match (down, current) {
+            (true, i) if i + 1 >= len => 0,
+            (true, i) => i + 1,
+            (false, 0) => len - 1,
+            (false, i) => i - 1,
+        };
+        self.select_folder(rows[self.folder_index].path.clone());
+    }
+
+    pub fn select_folder(&mut self, folder: String) {
+        self.selected_folder = folder;
+        self.filter_passwords();
+    }
+
+    // switch the arrow keys between the folder tree and the entry list:
+    pub fn toggle_folder_focus(&mut self) {
+        self.folder_focus = !self.folder_focus;
+    }
+
+    // fold or unfold the folder under the cursor:
+    pub fn toggle_folder_collapsed(&mut self) {
+        let rows = self.folder_rows();
+        if let Some(row) = rows.get(self.folder_index) {
+            if !row.has_children {
+                return;
+            }
+            if !self.collapsed_folders.remove(&row.path) {
+                self.collapsed_folders.insert(row.path.clone());
+            }
+        }
+    }
+
     // move the selection up:
     pub fn move_selection_up(&mut self) {
         let len = self.filtered_passwords.len();
@@ -118,39 +229,71 @@ impl App {
         let _ = restore_terminal();
     }
 
-    // Copies the password of the current selection to clipboard:
+    // Copies the password of the current selection to clipboard, or the main secret
+    // of other kinds (card number, private key, ...):
     pub fn copy_password(&mut self) {
         if let Some(entry) = self.selected_password() {
-            let mut clipboard = match Clipboard::new() {
-                Ok(ctx) => ctx,
-                Err(e) => {
-                    self.notification = Some(Notification {
-                        header: "Error".into(),
-                        message: format!("Could not access clipboard: {}", e),
-                        color: Color::Red,
-                        created: Instant::now(),
-                    });
-                    return;
-                }
-            };
-            let selected_password = entry.password.clone();
-            debug!("Selected password: {}", selected_password);
-            if let Err(e) = clipboard.set_text(selected_password) {
+            let Some(secret) = entry.primary_secret() else {
                 self.notification = Some(Notification {
                     header: "Error".into(),
-                    message: format!("Failed to copy to clipboard: {}", e),
+                    message: format!("Nothing to copy from {}", entry.name),
                     color: Color::Red,
                     created: Instant::now(),
                 });
                 return;
+            };
+            let what = match entry.kind.primary_field() {
+                Some(key) => entry.kind.fields().iter().find(|f| f.key == key).map_or(key, |f| f.label),
+                None if entry.kind == EntryKind::SecureNote => "Note",
+                None => "Password",
+            };
+            debug!("Copying password of {}", entry.name);
+            let secret = SecretString::from(secret);
+            let (id, name) = (entry.id.clone(), entry.name.clone());
+            if !self.set_clipboard(&secret) {
+                return;
             }
 
             self.notification = Some(Notification {
                 header: "Copied".into(),
-                message: format!("{} password copied!", entry.name),
+                message: format!("{} of {} copied!", what, name),
                 color: Color::Green,
                 created: Instant::now(),
             });
+            self.mark_used(&id);
+        }
+    }
+
+    // put `text` on the clipboard, showing what went wrong if it could not:
+    fn set_clipboard(&mut self, text: &str) -> bool {
+        let result = Clipboard::new()
+            .map_err(|e| format!("Could not access clipboard: {}", e))
+            .and_then(|mut clipboard| {
+                clipboard.set_text(text).map_err(|e| format!("Failed to copy to clipboard: {}", e))
+            });
+        if let Err(message) = result {
+            self.notification = Some(Notification {
+                header: "Error".into(),
+                message,
+                color: Color::Red,
+                created: Instant::now(),
+            });
+            return false;
+        }
+        true
+    }
+
+    // Stamp an entry as used just now. Failing only costs the timestamp, so it is
+    // logged rather than shown
+    fn mark_used(&mut self, id: &str) {
+        match record_use(&get_vault_path(), &self.encryption_key, &mut self.header, id) {
+            Ok(passwords) => {
+                let selected_index = self.selected_index;
+                self.set_passwords(passwords);
+                self.filter_passwords();
+                self.selected_index = selected_index.min(self.filtered_passwords.len().saturating_sub(1));
+            }
+            Err(e) => log::warn!("Failed to record use of entry: {}", e),
         }
     }
 
@@ -178,6 +321,14 @@ impl App {
     }
 
     pub fn handle_modal_input(&mut self, key: KeyEvent) {
+        if self.modal.as_ref().is_some_and(|modal| modal.typ == ModalType::History) {
+            self.handle_history_input(key);
+            return;
+        }
+        if self.modal.as_ref().is_some_and(|modal| modal.typ == ModalType::Trash) {
+            self.handle_trash_input(key);
+            return;
+        }
         if let Some(modal) = &mut self.modal {
             match key.code {
                 KeyCode::Tab => {
@@ -187,6 +338,28 @@ impl App {
                         modal.next_field();
                     }
                 }
+                KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::ALT) => {
+                    modal.add_custom_field();
+                }
+                KeyCode::Char('x') if key.modifiers.contains(KeyModifiers::ALT) => {
+                    modal.remove_custom_field();
+                }
+                KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::ALT) => {
+                    modal.cycle_custom_field_kind();
+                }
+                KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::ALT) => {
+                    modal.cycle_kind();
+                }
+                KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::ALT) && modal.has_custom_fields() => {
+                    if let Err(e) = modal.generate_into_active_field(&GeneratorOptions::default()) {
+                        self.notification = Some(Notification {
+                            header: "Generate".into(),
+                            message: e,
+                            color: Color::Red,
+                            created: Instant::now(),
+                        });
+                    }
+                }
                 KeyCode::Char(c) => modal.handle_input(c),
                 KeyCode::Backspace => modal.handle_backspace(),
                 _ => {}
@@ -194,6 +367,89 @@ impl App {
         }
     }
 
+    // keys of the history view; Enter (restore) and Esc go through confirm/close_modal:
+    fn handle_history_input(&mut self, key: KeyEvent) {
+        let Some(modal) = &mut self.modal else {
+            return;
+        };
+        match key.code {
+            KeyCode::Up => modal.move_list_selection(false),
+            KeyCode::Down => modal.move_list_selection(true),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::ALT) => {
+                if let Some(old) = modal.selected_history() {
+                    let value = old.value.clone();
+                    let position = modal.active_field + 1;
+                    if self.set_clipboard(&value) {
+                        self.notification = Some(Notification {
+                            header: "Copied".into(),
+                            message: format!("Password #{} copied!", position),
+                            color: Color::Green,
+                            created: Instant::now(),
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // keys of the trash view; Enter restores through confirm_modal:
+    fn handle_trash_input(&mut self, key: KeyEvent) {
+        let Some(modal) = &mut self.modal else {
+            return;
+        };
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Up => modal.move_list_selection(false),
+            KeyCode::Down => modal.move_list_selection(true),
+            KeyCode::Char('d') if alt => {
+                if let Some(entry) = modal.entries.get(modal.active_field) {
+                    let confirm = Modal::new_confirmation(
+                        ConfirmationType::Purge,
+                        " Confirm Purge ".into(),
+                        format!("Delete {} for good? This cannot be undone.", entry.name),
+                        Some(entry.clone()),
+                    );
+                    self.open_modal(confirm);
+                }
+            }
+            KeyCode::Char('x') if alt && !modal.entries.is_empty() => {
+                let confirm = Modal::new_confirmation(
+                    ConfirmationType::EmptyTrash,
+                    " Confirm Empty Trash ".into(),
+                    format!("Delete all {} entries in the trash for good? This cannot be undone.", modal.entries.len()),
+                    None,
+                );
+                self.open_modal(confirm);
+            }
+            _ => {}
+        }
+    }
+
+    // apply a change to the vault on disk and refresh the list from the saved result:
+    pub fn apply_change<F>(&mut self, change: F) -> bool
+        where F: FnOnce(&mut Vec<PasswordEntry>) -> Result<(), String>
+    {
+        match update_passwords(&get_vault_path(), &self.encryption_key, &mut self.header, change) {
+            Ok((passwords, ())) => {
+                self.set_passwords(passwords);
+                self.folder_index = self.folder_index.min(self.folder_rows().len() - 1);
+                self.filter_passwords();
+                true
+            }
+            Err(e) => {
+                log::error!("Failed to save passwords: {}", e);
+                self.notification = Some(Notification {
+                    header: "Error".into(),
+                    message: format!("Failed to save changes: {}", e),
+                    color: Color::Red,
+                    created: Instant::now(),
+                });
+                false
+            }
+        }
+    }
+
     // confirm the modal:
     pub fn confirm_modal(&mut self) {
         if let Some(modal) = self.modal.take() {
@@ -201,27 +457,15 @@ impl App {
                 // handle the delete confirmation:
                 ModalType::Confirm(ConfirmationType::Delete) => {
                     if let Some(entry) = modal.entry {
-                        self.all_passwords.retain(|p| p.id != entry.id);
-                        self.filter_passwords();
-                        self.notification = Some(Notification {
-                            header: "Deleted".into(),
-                            message: format!("{} deleted!", entry.name),
-                            color: Color::Red,
-                            created: Instant::now(),
+                        let id = entry.id.clone();
+                        let deleted = self.apply_change(move |passwords| {
+                            move_to_trash(passwords, |p| p.id == id);
+                            Ok(())
                         });
-                        // Save after deletion
-                        if
-                            let Err(e) = save_passwords(
-                                PASSWORD_FILE_PATH,
-                                &self.all_passwords,
-                                &self.encryption_key,
-                                &self.salt
-                            )
-                        {
-                            log::error!("Failed to save passwords: {}", e);
+                        if deleted {
                             self.notification = Some(Notification {
-                                header: "Error".into(),
-                                message: "Failed to save changes".into(),
+                                header: "Deleted".into(),
+                                message: format!("{} moved to the trash!", entry.name),
                                 color: Color::Red,
                                 created: Instant::now(),
                             });
@@ -234,114 +478,287 @@ impl App {
                     // Save IDs before clearing the selection
                     let selected_ids = self.multi_selected.clone();
 
-                    // Remove all selected entries
-                    self.all_passwords.retain(|p| !selected_ids.contains(&p.id));
-
-                    // Clear the selection and update display
-                    self.multi_selected.clear();
-                    self.filter_passwords();
-
-                    // Show notification
-                    self.notification = Some(Notification {
-                        header: "Deleted".into(),
-                        message: "Selected entries deleted!".into(),
-                        color: Color::Red,
-                        created: Instant::now(),
+                    // Move all selected entries to the trash
+                    let deleted = self.apply_change(move |passwords| {
+                        move_to_trash(passwords, |p| selected_ids.contains(&p.id));
+                        Ok(())
                     });
 
-                    // Save changes
-                    if
-                        let Err(e) = save_passwords(
-                            PASSWORD_FILE_PATH,
-                            &self.all_passwords,
-                            &self.encryption_key,
-                            &self.salt
-                        )
-                    {
-                        log::error!("Failed to save passwords: {}", e);
+                    if deleted {
+                        // Clear the selection
+                        self.multi_selected.clear();
+
+                        // Show notification
                         self.notification = Some(Notification {
-                            header: "Error".into(),
-                            message: "Failed to save changes".into(),
+                            header: "Deleted".into(),
+                            message: "Selected entries moved to the trash!".into(),
                             color: Color::Red,
                             created: Instant::now(),
                         });
                     }
                 }
 
-                ModalType::Input(input_type) => {
-                    let name = modal.input_fields[0].value.clone();
-                    let id = modal.input_fields[1].value.clone();
-                    let password = modal.input_fields[2].value.clone();
-                    let url = Some(modal.input_fields[3].value.clone());
-                    let notes = Some(modal.input_fields[4].value.clone());
+                // purge an entry from the trash for good:
+                ModalType::Confirm(ConfirmationType::Purge) => {
+                    if let Some(entry) = modal.entry {
+                        let id = entry.id.clone();
+                        let purged = self.apply_change(move |passwords| purge_from_trash(passwords, &id));
+                        if purged {
+                            self.notification = Some(Notification {
+                                header: "Purged".into(),
+                                message: format!("{} deleted for good!", entry.name),
+                                color: Color::Red,
+                                created: Instant::now(),
+                            });
+                        }
+                    }
+                }
 
-                    match input_type {
-                        InputType::Create => {
-                            let entry = PasswordEntry {
-                                name: name.clone(),
-                                id: id.clone(),
-                                password,
-                                metadata: Metadata { url, notes },
-                            };
-
-                            self.all_passwords.push(entry);
-                            self.filter_passwords();
+                ModalType::Confirm(ConfirmationType::EmptyTrash) => {
+                    let emptied = self.apply_change(|passwords| {
+                        empty_trash(passwords);
+                        Ok(())
+                    });
+                    if emptied {
+                        self.notification = Some(Notification {
+                            header: "Purged".into(),
+                            message: "Trash emptied!".into(),
+                            color: Color::Red,
+                            created: Instant::now(),
+                        });
+                    }
+                }
+
+                // restore the trashed entry under the cursor:
+                ModalType::Trash => {
+                    if let Some(entry) = modal.entries.get(modal.active_field) {
+                        let id = entry.id.clone();
+                        let restored = self.apply_change(move |passwords| restore_from_trash(passwords, &id));
+                        if restored {
                             self.notification = Some(Notification {
-                                header: "Created".into(),
-                                message: format!("{} created!", name),
+                                header: "Restored".into(),
+                                message: format!("{} restored!", entry.name),
                                 color: Color::Green,
                                 created: Instant::now(),
                             });
                         }
-                        InputType::Edit => {
-                            if let Some(entry) = modal.entry {
-                                if
-                                    let Some(existing_entry) = self.all_passwords
-                                        .iter_mut()
-                                        .find(|p| p.id == entry.id)
-                                {
-                                    existing_entry.name = name.clone();
-                                    existing_entry.id = id;
-                                    existing_entry.password = password;
-                                    existing_entry.metadata.url = url;
-                                    existing_entry.metadata.notes = notes;
-                                }
+                    }
+                }
 
-                                if !verify_password(&entry, self) {
-                                    return;
-                                }
+                // restore the history value under the cursor:
+                ModalType::History => {
+                    if let Some(entry) = &modal.entry {
+                        if entry.history.is_empty() {
+                            self.close_modal();
+                            return;
+                        }
+                        let (id, index) = (entry.id.clone(), modal.active_field);
+                        let restored = self.apply_change(move |passwords| {
+                            passwords
+                                .iter_mut()
+                                .find(|p| p.id == id)
+                                .ok_or("Entry was removed by another process")?
+                                .restore_from_history(index, SETTINGS.password_history_limit)
+                        });
+                        if restored {
+                            self.notification = Some(Notification {
+                                header: "Restored".into(),
+                                message: format!("Password #{} of {} restored!", index + 1, entry.name),
+                                color: Color::Yellow,
+                                created: Instant::now(),
+                            });
+                        }
+                    }
+                }
+
+                ModalType::Input(InputType::RenameFolder) => {
+                    let from = modal.content.clone();
+                    let to = normalize_folder(&modal.input_fields[0].value);
+                    let target = to.clone();
+                    let renamed = self.apply_change(move |passwords| {
+                        rename_folder(passwords, &from, &target).map(|_| ())
+                    });
+                    if renamed {
+                        self.collapsed_folders.clear();
+                        self.select_folder(to.clone());
+                        self.folder_index = self.folder_rows()
+                            .iter()
+                            .position(|row| row.path == to)
+                            .unwrap_or(0);
+                        self.notification = Some(Notification {
+                            header: "Moved".into(),
+                            message: format!("{} moved to {}", modal.content, if to.is_empty() {
+                                "the top level"
+                            } else {
+                                &to
+                            }),
+                            color: Color::Green,
+                            created: Instant::now(),
+                        });
+                    }
+                }
+
+                ModalType::Input(InputType::ChangePassword) => {
+                    let current = modal.input_fields[0].value.clone();
+                    let new_password = modal.input_fields[1].value.clone();
+                    let confirm = modal.input_fields[2].value.clone();
+                    self.change_master_password(&current, &new_password, &confirm);
+                }
+
+                ModalType::Input(input_type) => {
+                    let name = modal.entry_value(EntryField::Name).to_string();
+                    let username = modal.entry_value(EntryField::Username).to_string();
+                    let password = modal.entry_value(EntryField::Password);
+                    let url = Some(modal.entry_value(EntryField::Url).to_string());
+                    let notes = Some(modal.entry_value(EntryField::Notes).to_string());
+                    let folder = normalize_folder(&modal.entry_value(EntryField::Folder));
+                    let tags = parse_tags(&modal.entry_value(EntryField::Tags));
+                    let fields = match modal.custom_fields() {
+                        Ok(fields) => fields,
+                        Err(message) => {
+                            self.notification = Some(Notification {
+                                header: "Error".into(),
+                                message,
+                                color: Color::Red,
+                                created: Instant::now(),
+                            });
+                            return;
+                        }
+                    };
 
-                                self.filter_passwords();
+                    // The id never changes: edits keep the one of the original entry
+                    let mut entry = PasswordEntry::new(name.clone(), username, password);
+                    entry.kind = modal.kind;
+                    entry.details = modal.details();
+                    entry.folder = folder;
+                    entry.tags = tags;
+                    entry.metadata = Metadata { url, notes, fields };
+                    if let Some(original) = &modal.entry {
+                        entry.id = original.id.clone();
+                    }
+                    let warning = match verify_password(&entry) {
+                        Ok(warning) => warning,
+                        Err(message) => {
+                            self.notification = Some(Notification {
+                                header: "Error".into(),
+                                message,
+                                color: Color::Red,
+                                created: Instant::now(),
+                            });
+                            return;
+                        }
+                    };
+
+                    match input_type {
+                        InputType::Create => {
+                            let created = self.apply_change(move |passwords| {
+                                passwords.push(entry);
+                                Ok(())
+                            });
+                            if created {
                                 self.notification = Some(Notification {
-                                    header: "Updated".into(),
-                                    message: format!("{} updated!", name),
-                                    color: Color::Yellow,
+                                    header: "Created".into(),
+                                    message: format!("{} created!", name),
+                                    color: Color::Green,
                                     created: Instant::now(),
                                 });
+                                self.warn_weak_password(&name, warning);
                             }
                         }
-                    }
+                        InputType::Edit => {
+                            if let Some(original) = modal.entry {
+                                let updated = self.apply_change(move |passwords| {
+                                    let existing_entry = passwords
+                                        .iter_mut()
+                                        .find(|p| p.id == original.id)
+                                        .ok_or_else(|| {
+                                            format!(
+                                                "{} was removed by another process",
+                                                original.name
+                                            )
+                                        })?;
+                                    // Times carry over from the entry as saved now
+                                    entry.replace(existing_entry, SETTINGS.password_history_limit);
+                                    *existing_entry = entry;
+                                    Ok(())
+                                });
 
-                    // Save after create or edit
-                    if
-                        let Err(e) = save_passwords(
-                            PASSWORD_FILE_PATH,
-                            &self.all_passwords,
-                            &self.encryption_key,
-                            &self.salt
-                        )
-                    {
-                        log::error!("Failed to save passwords: {}", e);
-                        self.notification = Some(Notification {
-                            header: "Error".into(),
-                            message: "Failed to save changes".into(),
-                            color: Color::Red,
-                            created: Instant::now(),
-                        });
+                                if updated {
+                                    self.notification = Some(Notification {
+                                        header: "Updated".into(),
+                                        message: format!("{} updated!", name),
+                                        color: Color::Yellow,
+                                        created: Instant::now(),
+                                    });
+                                    self.warn_weak_password(&name, warning);
+                                }
+                            }
+                        }
+                        // handled in their own arms above
+                        InputType::ChangePassword | InputType::RenameFolder => {}
                     }
                 }
             }
         }
         self.close_modal();
     }
+
+    // with `password_strength_warn_only`, say that a weak password was saved anyway:
+    fn warn_weak_password(&mut self, name: &str, warning: Option<String>) {
+        if let Some(message) = warning {
+            self.notification = Some(Notification {
+                header: "Weak password".into(),
+                message: format!("{} saved. {}", name, message),
+                color: Color::Yellow,
+                created: Instant::now(),
+            });
+        }
+    }
+
+    // re-key the vault and swap in the new key:
+    pub fn change_master_password(&mut self, current: &str, new_password: &str, confirm: &str) {
+        let error = if new_password.is_empty() {
+            Some("New master password cannot be empty".to_string())
+        } else if new_password != confirm {
+            Some("New passwords do not match".to_string())
+        } else {
+            match change_master_password(
+                &get_vault_path(),
+                current,
+                new_password,
+                active_key_file().as_deref(),
+                // Other key slots need their secrets, which only `rsp passwd` asks for
+                &[]
+            ) {
+                Ok((passwords, key, header)) => {
+                    self.encryption_key = key;
+                    self.header = header;
+                    self.set_passwords(passwords);
+                    self.filter_passwords();
+
+                    // Cached credentials elsewhere were derived from the old password
+                    if let Err(e) = STATE_MANAGER.invalidate() {
+                        log::error!("Failed to invalidate cached credentials: {}", e);
+                    }
+                    None
+                }
+                Err(e) => Some(e),
+            }
+        };
+
+        self.notification = Some(match error {
+            Some(message) => Notification {
+                header: "Error".into(),
+                message,
+                color: Color::Red,
+                created: Instant::now(),
+            },
+            None => Notification {
+                header: "Updated".into(),
+                message: "Master password changed!".into(),
+                color: Color::Green,
+                created: Instant::now(),
+            },
+        });
+    }
 }
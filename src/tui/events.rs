@@ -1,14 +1,12 @@
 use crate::tui::app::App;
 use crate::tui::keybindings::{AppEvent, KeyBindings};
-use crate::utils::fuzzy_finder::fuzzy_match;
-use crate::models::structs::PasswordEntry;
 
 use crossterm::event::{self, KeyCode, KeyEvent};
 use ratatui::style::Color;
 use std::time::{Duration, Instant};
 
 use super::widgets::notification::Notification;
-use super::widgets::{modal::ConfirmationType, modal::InputType, modal::Modal};
+use super::widgets::{modal::ConfirmationType, modal::EntryField, modal::InputType, modal::Modal};
 
 // struct for the event handler:
 pub struct EventHandler {
@@ -58,6 +56,8 @@ impl EventHandler {
     fn handle_action(&self, action: AppEvent, app: &mut App) {
         match action {
             AppEvent::Quit => app.quit(),
+            AppEvent::MoveUp if app.folder_focus => app.move_folder_selection(false),
+            AppEvent::MoveDown if app.folder_focus => app.move_folder_selection(true),
             AppEvent::MoveUp => app.move_selection_up(),
             AppEvent::MoveDown => app.move_selection_down(),
             AppEvent::ToggleHelp => app.toggle_help(),
@@ -68,18 +68,7 @@ impl EventHandler {
             // handle the backspace event:
             AppEvent::Backspace => {
                 app.search_input.pop();
-                let search = app.search_input.to_lowercase();
-                app.filtered_passwords = app
-                    .all_passwords
-                    .iter()
-                    .filter(|p| {
-                        let name = p.name.to_lowercase();
-                        let id = p.id.to_lowercase();
-                        fuzzy_match(&search, &name) || fuzzy_match(&search, &id)
-                    })
-                    .cloned()
-                    .collect();
-                app.selected_index = 0;
+                app.filter_passwords();
             }
 
             // handle the copy password event:
@@ -97,18 +86,10 @@ impl EventHandler {
                         created: Instant::now(),
                     });
                 } else if let Some(entry) = app.selected_password() {
-                    // Create a new PasswordEntry to avoid type mismatch
-                    let entry_clone = PasswordEntry {
-                        name: entry.name.clone(),
-                        id: entry.id.clone(),
-                        password: entry.password.clone(),
-                        metadata: entry.metadata.clone(),
-                    };
-                    
                     app.open_modal(Modal::new_input(
                         InputType::Edit,
                         " Edit Entry ".into(),
-                        Some(entry_clone),
+                        Some(entry.clone()),
                     ));
                 }
             }
@@ -117,19 +98,11 @@ impl EventHandler {
             AppEvent::DeleteEntry | AppEvent::BulkDelete => {
                 if app.multi_selected.is_empty() {
                     if let Some(entry) = app.selected_password() {
-                        // Create a new PasswordEntry to avoid type mismatch
-                        let entry_clone = PasswordEntry {
-                            name: entry.name.clone(),
-                            id: entry.id.clone(),
-                            password: entry.password.clone(),
-                            metadata: entry.metadata.clone(),
-                        };
-                        
                         app.open_modal(Modal::new_confirmation(
                             ConfirmationType::Delete,
                             " Confirm Delete ".into(),
-                            format!("Are you sure you want to delete {}?", entry.name),
-                            Some(entry_clone),
+                            format!("Move {} to the trash?", entry.name),
+                            Some(entry.clone()),
                         ));
                     }
                 } else {
@@ -137,11 +110,11 @@ impl EventHandler {
                         .filtered_passwords
                         .iter()
                         .filter(|entry| app.multi_selected.contains(&entry.id))
-                        .map(|entry| format!("{} | {}", entry.name, entry.id))
+                        .map(|entry| format!("{} | {}", entry.name, entry.username))
                         .collect();
                     let entries_list = selected_entries.join("\n- ");
                     let message = format!(
-                        "Are you sure you want to delete these entries?\n\n- {}",
+                        "Move these entries to the trash?\n\n- {}",
                         entries_list
                     );
                     app.open_modal(Modal::new_confirmation(
@@ -155,11 +128,50 @@ impl EventHandler {
 
             // handle the create entry event:
             AppEvent::CreateEntry => {
-                app.open_modal(Modal::new_input(
-                    InputType::Create,
-                    " Create Entry ".into(),
-                    None,
-                ));
+                let mut modal = Modal::new_input(InputType::Create, " Create Entry ".into(), None);
+                // New entries go into the folder being looked at
+                modal.set_entry_value(EntryField::Folder, &app.selected_folder);
+                app.open_modal(modal);
+            }
+
+            // handle the folder tree events:
+            AppEvent::FocusFolders => app.toggle_folder_focus(),
+            AppEvent::ToggleFolder => {
+                if app.folder_focus {
+                    app.toggle_folder_collapsed();
+                }
+            }
+            AppEvent::RenameFolder => {
+                if app.selected_folder.is_empty() {
+                    app.notification = Some(Notification {
+                        header: "Error".into(),
+                        message: "Select a folder in the folder tree (Alt+f) first".into(),
+                        color: Color::Red,
+                        created: Instant::now(),
+                    });
+                } else {
+                    let folder = app.selected_folder.clone();
+                    app.open_modal(Modal::new_rename_folder(" Move Folder ".into(), &folder));
+                }
+            }
+
+            // handle the password history event:
+            AppEvent::ShowHistory => {
+                if let Some(entry) = app.selected_password() {
+                    let title = format!(" History of {} ", entry.name);
+                    app.open_modal(Modal::new_history(title, entry.clone()));
+                }
+            }
+
+            // handle the trash event:
+            AppEvent::ShowTrash => {
+                let title = format!(" Trash ({}) ", app.trash.len());
+                app.open_modal(Modal::new_trash(title, app.trash.clone()));
+            }
+
+            // handle the change master password event:
+            AppEvent::ChangePassword => {
+                app.open_modal(Modal::new_change_password(" Change Master Password ".into()));
             }
 
             // handle the multi select event:
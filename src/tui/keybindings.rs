@@ -14,8 +14,14 @@ pub enum AppEvent {
     DeleteEntry,      // Alt+d
     BulkDelete,       // Alt+b
     CreateEntry,      // Alt+n
+    ChangePassword,   // Alt+p
     MultiSelect,      // Tab: mark current & move to next
     CloseModal,       // Esc: close modal
+    FocusFolders,     // Alt+f: move the arrow keys to the folder tree and back
+    ToggleFolder,     // Enter: fold or unfold the folder under the cursor
+    RenameFolder,     // Alt+r: move or rename the folder under the cursor
+    ShowHistory,      // Alt+y: earlier passwords of the selected entry
+    ShowTrash,        // Alt+t: deleted entries, to restore or purge
 }
 
 // default implementation for the key bindings:
@@ -56,6 +62,10 @@ impl KeyBindings {
             return Some(AppEvent::Backspace);
         }
 
+        if key.code == KeyCode::Enter {
+            return Some(AppEvent::ToggleFolder);
+        }
+
         // Handle Tab for multi-selection only when no modal is active
         if key.code == KeyCode::Tab && !key.modifiers.contains(KeyModifiers::SHIFT) {
             return Some(AppEvent::MultiSelect);
@@ -68,6 +78,11 @@ impl KeyBindings {
                 KeyCode::Char('e') => return Some(AppEvent::EditEntry),
                 KeyCode::Char('d') => return Some(AppEvent::DeleteEntry),
                 KeyCode::Char('n') => return Some(AppEvent::CreateEntry),
+                KeyCode::Char('p') => return Some(AppEvent::ChangePassword),
+                KeyCode::Char('f') => return Some(AppEvent::FocusFolders),
+                KeyCode::Char('r') => return Some(AppEvent::RenameFolder),
+                KeyCode::Char('y') => return Some(AppEvent::ShowHistory),
+                KeyCode::Char('t') => return Some(AppEvent::ShowTrash),
                 _ => {}
             }
         }
@@ -6,35 +6,43 @@ pub mod widgets;
 
 use crossterm::event::{KeyCode, KeyEvent};
 use log::error;
-use rand::RngCore;
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::{
     fs::File,
     io::{self, Read, Seek, SeekFrom},
+    path::Path,
 };
 
 use crate::{
-    auth::handler::derive_key,
-    data::data::{create_password_file, load_passwords, save_passwords},
-    models::structs::PasswordEntry,
+    auth::keyfile::active_key_file,
+    config::settings::SETTINGS,
+    data::data::{create_vault, load_passwords},
+    models::secret::SecretString,
+    models::structs::{PasswordEntry, VaultHeader},
+    models::types::EncryptionKey,
     state::manager::STATE_MANAGER,
+    data::vault::{
+        find_legacy_vault, get_vault_path, get_vault_path_for, import_legacy_vault,
+        legacy_vault_hint, DEFAULT_VAULT_NAME,
+    },
     tui::{
         app::App,
         events::EventHandler,
         layout::{restore_terminal, setup_terminal},
         widgets::ui::render_ui,
     },
-    PASSWORD_FILE_PATH,
 };
 
 // Simple password input function
 fn get_master_password(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-) -> io::Result<String> {
-    let mut password = String::new();
+) -> io::Result<SecretString> {
+    let mut password = SecretString::new();
     let mut events = EventHandler::new();
     // Create a dummy App instance for event handling during password input
-    let mut dummy_app = App::new(Vec::new(), [0; 32], Vec::new());
+    let mut dummy_app = App::new(Vec::new(), EncryptionKey::default(), VaultHeader::new(Vec::new()));
+    // The key file from --keyfile is combined with the password
+    let key_file_hint = if active_key_file().is_some() { " (+ key file)" } else { "" };
     loop {
         terminal.draw(|f| {
             let size = f.area();
@@ -43,7 +51,8 @@ fn get_master_password(
 
             f.render_widget(
                 ratatui::widgets::Paragraph::new(format!(
-                    "Enter master password: {}",
+                    "Enter master password{}: {}",
+                    key_file_hint,
                     "*".repeat(password.len())
                 ))
                 .block(ratatui::widgets::Block::default().borders(ratatui::widgets::Borders::ALL)),
@@ -80,16 +89,49 @@ fn get_master_password(
     Ok(password)
 }
 
+// Ask whether to move a vault left by an older version into the data dir
+fn confirm_legacy_import(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    legacy: &Path,
+) -> io::Result<bool> {
+    let mut events = EventHandler::new();
+    let mut dummy_app = App::new(Vec::new(), EncryptionKey::default(), VaultHeader::new(Vec::new()));
+    let target = get_vault_path_for(DEFAULT_VAULT_NAME);
+    loop {
+        terminal.draw(|f| {
+            f.render_widget(ratatui::widgets::Clear, f.area());
+            f.render_widget(
+                ratatui::widgets::Paragraph::new(format!(
+                    "Found a vault from an older version at {}.\nMove it to {}? (y/n)",
+                    legacy.display(),
+                    target.display()
+                ))
+                .block(ratatui::widgets::Block::default().borders(ratatui::widgets::Borders::ALL)),
+                f.area(),
+            );
+        })?;
+        if let Some(event) = events.next_event(&mut dummy_app) {
+            match event.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => return Ok(true),
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => return Ok(false),
+                _ => {}
+            }
+        }
+    }
+}
+
 pub fn run_tui() -> io::Result<()> {
     // Set up the terminal
     let mut terminal = setup_terminal()?;
 
+    let vault_path = get_vault_path();
+
     // Check if already unlocked
     let start_unlocked = STATE_MANAGER.is_unlocked();
 
-    let (passwords, key, salt) = if start_unlocked {
+    let (passwords, key, header) = if start_unlocked {
         let state = STATE_MANAGER.get_state()?;
-        (state.passwords, state.encryption_key, state.salt)
+        (state.passwords, state.encryption_key, state.header)
     } else {
         // Show loading message
         terminal.draw(|f| {
@@ -100,9 +142,18 @@ pub fn run_tui() -> io::Result<()> {
         })?;
 
         // Implement unlock flow
-        let result = (|| -> io::Result<(Vec<PasswordEntry>, [u8; 32], Vec<u8>)> {
-            if std::path::Path::new(PASSWORD_FILE_PATH).exists() {
-                let mut file = File::open(PASSWORD_FILE_PATH)?;
+        let key_file = active_key_file();
+        let result = (|| -> io::Result<(Vec<PasswordEntry>, EncryptionKey, VaultHeader)> {
+            // Never start an empty vault next to one an older version left behind
+            if let Some(legacy) = find_legacy_vault() {
+                if !confirm_legacy_import(&mut terminal, &legacy)? {
+                    return Err(io::Error::new(io::ErrorKind::Other, legacy_vault_hint(&legacy)));
+                }
+                import_legacy_vault(&legacy)?;
+            }
+
+            if vault_path.exists() {
+                let mut file = File::open(&vault_path)?;
                 let mut first_char = [0u8; 1];
                 file.read_exact(&mut first_char)?;
                 file.seek(SeekFrom::Start(0))?;
@@ -127,21 +178,14 @@ pub fn run_tui() -> io::Result<()> {
 
                     std::thread::sleep(std::time::Duration::from_secs(2));
                     let password = get_master_password(&mut terminal)?;
-                    let mut salt = vec![0u8; 16];
-                    rand::rng().fill_bytes(&mut salt);
-                    let key: [u8; 32] = derive_key(&password, &salt)
-                        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-
-                    save_passwords(PASSWORD_FILE_PATH, &passwords, &key, &salt)
-                        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-
-                    return Ok((passwords, key, salt));
+                    return create_vault(&vault_path, &password, key_file.as_deref(), SETTINGS.vault_cipher, passwords)
+                        .map_err(|e| io::Error::new(io::ErrorKind::Other, e));
                 } else {
                     loop {
                         let password = get_master_password(&mut terminal)?;
-                        match load_passwords(PASSWORD_FILE_PATH, &password) {
-                            Ok((passwords, key, salt)) => {
-                                return Ok((passwords, key, salt));
+                        match load_passwords(&vault_path, &password, key_file.as_deref()) {
+                            Ok((passwords, key, header)) => {
+                                return Ok((passwords, key, header));
                             }
                             Err(e) => {
                                 terminal.draw(|f| {
@@ -179,7 +223,7 @@ pub fn run_tui() -> io::Result<()> {
                 })?;
                 std::thread::sleep(std::time::Duration::from_secs(1));
                 let password = get_master_password(&mut terminal)?;
-                create_password_file(PASSWORD_FILE_PATH, &password).map_err(|e| {
+                create_vault(&vault_path, &password, key_file.as_deref(), SETTINGS.vault_cipher, Vec::new()).map_err(|e| {
                     error!("Failed to create password file: {}", e);
                     io::Error::new(io::ErrorKind::Other, e)
                 })
@@ -196,7 +240,7 @@ pub fn run_tui() -> io::Result<()> {
     };
 
     // Run the TUI with the retrieved passwords
-    let mut app = App::new(passwords, key, salt);
+    let mut app = App::new(passwords, key, header);
     let mut events = EventHandler::new();
 
     // Clear the terminal completely before starting the main app loop
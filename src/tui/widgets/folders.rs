@@ -0,0 +1,53 @@
+use crate::tui::app::App;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem},
+    Frame,
+};
+
+pub fn render_folder_tree(f: &mut Frame, app: &App, area: Rect) {
+    // Clear the area before re-rendering the tree
+    f.render_widget(Clear, area);
+
+    let rows = app.folder_rows();
+    let items: Vec<ListItem> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let marker = match (row.has_children, row.collapsed) {
+                (true, true) => "▸ ",
+                (true, false) => "▾ ",
+                (false, _) => "  ",
+            };
+            let name = if row.path.is_empty() {
+                "All entries"
+            } else {
+                row.path.rsplit('/').next().unwrap_or(&row.path)
+            };
+            let is_selected = row.path == app.selected_folder;
+            let color = if is_selected && app.folder_focus && i == app.folder_index {
+                Color::Cyan
+            } else if is_selected {
+                Color::Yellow
+            } else {
+                Color::White
+            };
+            ListItem::new(format!("{}{}{}", "  ".repeat(row.depth.saturating_sub(1)), marker, name))
+                .style(Style::default().fg(color))
+        })
+        .collect();
+
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(" Folders ")
+            .border_style(Style::default().fg(if app.folder_focus {
+                Color::Cyan
+            } else {
+                Color::White
+            })),
+    );
+    f.render_widget(list, area);
+}
@@ -17,9 +17,22 @@ Alt+h      Toggle help
 q/Esc       Quit
 Alt+c       Copy password
 Alt+e       Edit entry
-Alt+d       Delete entry (or delete multi-selected)
+Alt+d       Move entry (or multi-selected) to trash
 Alt+n       Create new entry
+Alt+p       Change master password
 Tab         Multi-select current & move to next
+Alt+f       Switch ↑/↓ between folder tree and list
+Enter       Fold or unfold folder (in folder tree)
+Alt+r       Move or rename selected folder
+Alt+t       Trash (Enter restore, Alt+d purge, Alt+x empty)
+Alt+y       Password history (Alt+c copy, Enter restore)
+
+In the entry editor:
+Alt+k       Change the entry kind
+Alt+g       Generate a password into the field
+Alt+a       Add a custom field
+Alt+x       Remove the custom field
+Alt+t       Change the custom field's type
 ";
 
     let help = Paragraph::new(help_text).block(
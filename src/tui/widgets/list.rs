@@ -1,3 +1,6 @@
+use crate::config::settings::SETTINGS;
+use crate::data::vault::active_vault_name;
+use crate::models::kinds::EntryKind;
 use crate::tui::app::App;
 use ratatui::{
     layout::Rect,
@@ -40,13 +43,21 @@ pub fn render_password_list(f: &mut Frame, app: &App, area: Rect) {
                         }),
                     ),
                     Span::styled(
-                        format!("{} | {}", entry.name, entry.id),
+                        match entry.kind {
+                            EntryKind::Login => format!("{} | {}", entry.name, entry.username),
+                            kind => format!("{} | {}", entry.name, kind.label()),
+                        },
                         Style::default().fg(if is_selected {
                             Color::Cyan
                         } else {
                             Color::White
                         }),
                     ),
+                    // Flag secrets due for rotation
+                    Span::styled(
+                        if entry.is_password_stale(SETTINGS.password_max_age_days) { " (old)" } else { "" },
+                        Style::default().fg(Color::Red),
+                    ),
                 ]);
 
                 ListItem::new(line)
@@ -58,7 +69,7 @@ pub fn render_password_list(f: &mut Frame, app: &App, area: Rect) {
         Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
-            .title(" Passwords "),
+            .title(format!(" Passwords [{}] ", active_vault_name())),
     );
     f.render_widget(list, area);
 }
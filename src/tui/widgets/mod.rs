@@ -1,3 +1,4 @@
+pub mod folders;
 pub mod help;
 pub mod layout;
 pub mod list;
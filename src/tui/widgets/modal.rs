@@ -1,4 +1,9 @@
-use crate::models::structs::PasswordEntry;
+use crate::models::kinds::{DetailField, EntryKind};
+use crate::models::secret::SecretString;
+use crate::models::structs::{CustomField, FieldKind, PasswordEntry, PasswordHistoryEntry, Timestamps};
+use crate::config::settings::SETTINGS;
+use crate::utils::generator::GeneratorOptions;
+use crate::utils::strength::{estimate, Strength};
 use ratatui::{
     layout::Rect,
     style::{Color, Modifier, Style},
@@ -7,24 +12,35 @@ use ratatui::{
     Frame,
 };
 
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
 use super::layout::centered_rect;
 
 #[derive(PartialEq)]
 pub enum ModalType {
     Confirm(ConfirmationType),
     Input(InputType),
+    // earlier passwords of `entry`; `active_field` is the one under the cursor
+    History,
+    // the trashed `entries`; `active_field` is the one under the cursor
+    Trash,
 }
 
 #[derive(PartialEq)]
 pub enum ConfirmationType {
     Delete,
     BulkDelete,
+    Purge,
+    EmptyTrash,
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum InputType {
     Edit,
     Create,
+    ChangePassword,
+    RenameFolder,
 }
 
 pub struct Modal {
@@ -32,14 +48,120 @@ pub struct Modal {
     pub title: String,
     pub content: String,
     pub entry: Option<PasswordEntry>,
+    /// Entries the trash view lists.
+    pub entries: Vec<PasswordEntry>,
+    /// Kind of the entry being created or edited; decides which rows the form has.
+    pub kind: EntryKind,
     pub input_fields: Vec<InputField>,
     pub active_field: usize,
+    // values of rows the current kind has no use for, kept in case the kind changes back
+    stashed_fields: Vec<InputField>,
 }
 
 pub struct InputField {
     pub label: String,
-    pub value: String,
+    // Every field is a secret buffer, so typed text is wiped when the modal closes
+    pub value: SecretString,
     pub is_password: bool,
+    pub role: FieldRole,
+}
+
+// Custom fields of an entry take two rows: the field name, then its value
+#[derive(Clone, Copy, PartialEq)]
+pub enum FieldRole {
+    Fixed,
+    Entry(EntryField),
+    // one of the kind's `DetailField`s, by key
+    Detail(&'static str),
+    CustomName,
+    CustomValue(FieldKind),
+}
+
+// Values every entry form has, whatever the kind
+#[derive(Clone, Copy, PartialEq)]
+pub enum EntryField {
+    Name,
+    Username,
+    Password,
+    Url,
+    Notes,
+    Folder,
+    Tags,
+}
+
+impl InputField {
+    fn new(label: &str, value: SecretString, is_password: bool) -> Self {
+        Self {
+            label: label.into(),
+            value,
+            is_password,
+            role: FieldRole::Fixed,
+        }
+    }
+
+    fn entry(field: EntryField, label: &str, value: SecretString) -> Self {
+        Self {
+            label: label.into(),
+            value,
+            is_password: field == EntryField::Password,
+            role: FieldRole::Entry(field),
+        }
+    }
+
+    fn detail(field: &'static DetailField, value: SecretString) -> Self {
+        Self {
+            label: field.label.into(),
+            value,
+            is_password: field.kind.is_secret(),
+            role: FieldRole::Detail(field.key),
+        }
+    }
+
+    fn custom_name(name: &str) -> Self {
+        Self {
+            label: "Field name".into(),
+            value: name.into(),
+            is_password: false,
+            role: FieldRole::CustomName,
+        }
+    }
+
+    fn custom_value(kind: FieldKind, value: SecretString) -> Self {
+        Self {
+            label: format!("  Value [{}]", kind.as_str()),
+            value,
+            is_password: kind.is_secret(),
+            role: FieldRole::CustomValue(kind),
+        }
+    }
+}
+
+// The rows of an entry form for `kind`, filled from `entry` when editing: logins ask
+// for a username, password and URL, other kinds for their own fields
+fn entry_rows(kind: EntryKind, entry: Option<&PasswordEntry>) -> Vec<InputField> {
+    let value = |get: fn(&PasswordEntry) -> SecretString| entry.map(get).unwrap_or_default();
+    let mut rows = vec![InputField::entry(EntryField::Name, "Name", value(|e| e.name.as_str().into()))];
+    if kind == EntryKind::Login {
+        rows.push(InputField::entry(EntryField::Username, "Username", value(|e| e.username.as_str().into())));
+        rows.push(InputField::entry(EntryField::Password, "Password", value(|e| e.password.clone())));
+        rows.push(InputField::entry(
+            EntryField::Url,
+            "URL",
+            value(|e| e.metadata.url.as_deref().unwrap_or_default().into()),
+        ));
+    }
+    for field in kind.fields() {
+        let detail = entry.and_then(|e| e.details.get(field.key)).cloned().unwrap_or_default();
+        rows.push(InputField::detail(field, detail));
+    }
+    rows.push(InputField::entry(
+        EntryField::Notes,
+        if kind == EntryKind::SecureNote { "Note" } else { "Notes" },
+        value(|e| e.metadata.notes.as_deref().unwrap_or_default().into()),
+    ));
+    rows.push(InputField::entry(EntryField::Folder, "Folder", value(|e| e.folder.as_str().into())));
+    rows.push(InputField::entry(EntryField::Tags, "Tags", value(|e| e.tags.join(", ").into())));
+    rows
 }
 
 impl Modal {
@@ -54,79 +176,117 @@ impl Modal {
             title,
             content,
             entry,
+            entries: Vec::new(),
+            kind: EntryKind::Login,
             input_fields: Vec::new(),
             active_field: 0,
+            stashed_fields: Vec::new(),
         }
     }
 
     pub fn new_input(typ: InputType, title: String, entry: Option<PasswordEntry>) -> Self {
-        let input_fields = match &entry {
-            Some(e) => vec![
-                InputField {
-                    label: "Name".into(),
-                    value: e.name.clone(),
-                    is_password: false,
-                },
-                InputField {
-                    label: "ID".into(),
-                    value: e.id.clone(),
-                    is_password: false,
-                },
-                InputField {
-                    label: "Password".into(),
-                    value: e.password.clone(),
-                    is_password: true,
-                },
-                InputField {
-                    label: "URL".into(),
-                    value: e.metadata.url.clone().unwrap_or_default(),
-                    is_password: false,
-                },
-                InputField {
-                    label: "Notes".into(),
-                    value: e.metadata.notes.clone().unwrap_or_default(),
-                    is_password: false,
-                },
-            ],
-            None => vec![
-                InputField {
-                    label: "Name".into(),
-                    value: String::new(),
-                    is_password: false,
-                },
-                InputField {
-                    label: "ID".into(),
-                    value: String::new(),
-                    is_password: false,
-                },
-                InputField {
-                    label: "Password".into(),
-                    value: String::new(),
-                    is_password: true,
-                },
-                InputField {
-                    label: "URL".into(),
-                    value: String::new(),
-                    is_password: false,
-                },
-                InputField {
-                    label: "Notes".into(),
-                    value: String::new(),
-                    is_password: false,
-                },
-            ],
-        };
+        let kind = entry.as_ref().map_or(EntryKind::Login, |e| e.kind);
+        let mut input_fields = entry_rows(kind, entry.as_ref());
+        if let Some(e) = &entry {
+            for field in &e.metadata.fields {
+                input_fields.push(InputField::custom_name(&field.name));
+                input_fields.push(InputField::custom_value(field.kind, field.value.clone()));
+            }
+        }
 
         Self {
             typ: ModalType::Input(typ),
             title,
             content: String::new(),
             entry,
+            entries: Vec::new(),
+            kind,
             input_fields,
             active_field: 0,
+            stashed_fields: Vec::new(),
         }
     }
 
+    pub fn new_change_password(title: String) -> Self {
+        let input_fields = ["Current password", "New password", "Confirm new password"]
+            .iter()
+            .map(|label| InputField::new(label, SecretString::new(), true))
+            .collect();
+
+        Self {
+            typ: ModalType::Input(InputType::ChangePassword),
+            title,
+            content: String::new(),
+            entry: None,
+            entries: Vec::new(),
+            kind: EntryKind::Login,
+            input_fields,
+            active_field: 0,
+            stashed_fields: Vec::new(),
+        }
+    }
+
+    // the old path is kept in `content`, the new one is typed into the only field:
+    pub fn new_rename_folder(title: String, folder: &str) -> Self {
+        Self {
+            typ: ModalType::Input(InputType::RenameFolder),
+            title,
+            content: folder.to_string(),
+            entry: None,
+            entries: Vec::new(),
+            kind: EntryKind::Login,
+            input_fields: vec![InputField::new("New path", folder.into(), false)],
+            active_field: 0,
+            stashed_fields: Vec::new(),
+        }
+    }
+
+    pub fn new_history(title: String, entry: PasswordEntry) -> Self {
+        Self {
+            typ: ModalType::History,
+            title,
+            content: String::new(),
+            entry: Some(entry),
+            entries: Vec::new(),
+            kind: EntryKind::Login,
+            input_fields: Vec::new(),
+            active_field: 0,
+            stashed_fields: Vec::new(),
+        }
+    }
+
+    pub fn new_trash(title: String, entries: Vec<PasswordEntry>) -> Self {
+        Self {
+            typ: ModalType::Trash,
+            title,
+            content: String::new(),
+            entry: None,
+            entries,
+            kind: EntryKind::Login,
+            input_fields: Vec::new(),
+            active_field: 0,
+            stashed_fields: Vec::new(),
+        }
+    }
+
+    // move the cursor of the history or trash view, stopping at either end:
+    pub fn move_list_selection(&mut self, down: bool) {
+        let len = match self.typ {
+            ModalType::Trash => self.entries.len(),
+            _ => self.entry.as_ref().map_or(0, |entry| entry.history.len()),
+        };
+        self.active_field = if down {
+            (self.active_field + 1).min(len.saturating_sub(1))
+        } else {
+            self.active_field.saturating_sub(1)
+        };
+    }
+
+    // the history value under the cursor:
+    pub fn selected_history(&self) -> Option<&PasswordHistoryEntry> {
+        self.entry.as_ref()?.history.get(self.active_field)
+    }
+
     pub fn next_field(&mut self) {
         self.active_field = (self.active_field + 1) % self.input_fields.len();
     }
@@ -150,6 +310,160 @@ impl Modal {
             field.value.pop();
         }
     }
+
+    // only entry modals have custom fields:
+    pub fn has_custom_fields(&self) -> bool {
+        matches!(self.typ, ModalType::Input(InputType::Create | InputType::Edit))
+    }
+
+    // Switch the form to the next kind. Rows both kinds share keep their values; the
+    // others are put aside so cycling back to a kind brings them back
+    pub fn cycle_kind(&mut self) {
+        if !self.has_custom_fields() {
+            return;
+        }
+        self.kind = self.kind.next();
+        let custom_start = self
+            .input_fields
+            .iter()
+            .position(|row| matches!(row.role, FieldRole::CustomName))
+            .unwrap_or(self.input_fields.len());
+        let custom_rows = self.input_fields.split_off(custom_start);
+        let old_rows = std::mem::take(&mut self.input_fields);
+        self.stashed_fields.extend(old_rows);
+
+        let mut rows = entry_rows(self.kind, None);
+        for row in &mut rows {
+            if let Some(i) = self.stashed_fields.iter().rposition(|old| old.role == row.role) {
+                row.value = self.stashed_fields.remove(i).value;
+            }
+        }
+        rows.extend(custom_rows);
+        self.input_fields = rows;
+        self.active_field = 0;
+    }
+
+    // typed value of one of the rows every entry form has, empty if the kind has no such row:
+    pub fn entry_value(&self, field: EntryField) -> SecretString {
+        self.input_fields
+            .iter()
+            .find(|row| row.role == FieldRole::Entry(field))
+            .map(|row| row.value.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set_entry_value(&mut self, field: EntryField, value: &str) {
+        if let Some(row) = self.input_fields.iter_mut().find(|row| row.role == FieldRole::Entry(field)) {
+            row.value = value.into();
+        }
+    }
+
+    // Replace the secret under the cursor with a freshly generated one. Like
+    // `rsp add --generate`, only free-form secrets qualify: the password, an API
+    // secret or a hidden custom field
+    pub fn generate_into_active_field(&mut self, options: &GeneratorOptions) -> Result<(), String> {
+        let kind = self.kind;
+        let row = self
+            .input_fields
+            .get_mut(self.active_field)
+            .filter(|row| match row.role {
+                FieldRole::Entry(EntryField::Password) | FieldRole::CustomValue(FieldKind::Hidden) => true,
+                FieldRole::Detail(key) => kind == EntryKind::ApiCredential && kind.primary_field() == Some(key),
+                _ => false,
+            })
+            .ok_or("Move to a password field to generate one")?;
+        row.value = options.generate()?;
+        Ok(())
+    }
+
+    // strength of the login password being typed, judged with the name and username
+    // as values an attacker may know:
+    pub fn password_strength(&self) -> Option<Strength> {
+        if !self.has_custom_fields() || self.kind != EntryKind::Login {
+            return None;
+        }
+        let name = self.entry_value(EntryField::Name);
+        let username = self.entry_value(EntryField::Username);
+        let password = self.entry_value(EntryField::Password);
+        Some(estimate(password.expose(), &[name.expose(), username.expose()]))
+    }
+
+    // values typed into the kind's own rows, leaving out empty ones:
+    pub fn details(&self) -> BTreeMap<String, SecretString> {
+        self.input_fields
+            .iter()
+            .filter_map(|row| match row.role {
+                FieldRole::Detail(key) if !row.value.is_empty() => Some((key.to_string(), row.value.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // first row of the custom field under the cursor, if it is on one:
+    fn active_custom_field(&self) -> Option<usize> {
+        match self.input_fields.get(self.active_field)?.role {
+            FieldRole::Fixed | FieldRole::Entry(_) | FieldRole::Detail(_) => None,
+            FieldRole::CustomName => Some(self.active_field),
+            FieldRole::CustomValue(_) => Some(self.active_field - 1),
+        }
+    }
+
+    // append an empty text field and move the cursor to its name:
+    pub fn add_custom_field(&mut self) {
+        if !self.has_custom_fields() {
+            return;
+        }
+        self.input_fields.push(InputField::custom_name(""));
+        self.input_fields
+            .push(InputField::custom_value(FieldKind::Text, SecretString::new()));
+        self.active_field = self.input_fields.len() - 2;
+    }
+
+    // drop the custom field under the cursor:
+    pub fn remove_custom_field(&mut self) {
+        if let Some(first) = self.active_custom_field() {
+            self.input_fields.drain(first..first + 2);
+            self.active_field = first - 1;
+        }
+    }
+
+    // switch the custom field under the cursor to the next type:
+    pub fn cycle_custom_field_kind(&mut self) {
+        if let Some(first) = self.active_custom_field() {
+            let row = &mut self.input_fields[first + 1];
+            if let FieldRole::CustomValue(kind) = row.role {
+                let value = std::mem::take(&mut row.value);
+                *row = InputField::custom_value(kind.next(), value);
+            }
+        }
+    }
+
+    // custom fields as typed, skipping rows left completely empty:
+    pub fn custom_fields(&self) -> Result<Vec<CustomField>, String> {
+        let mut fields = Vec::new();
+        for pair in self.input_fields.windows(2) {
+            let (name, value) = (&pair[0], &pair[1]);
+            let kind = match (name.role, value.role) {
+                (FieldRole::CustomName, FieldRole::CustomValue(kind)) => kind,
+                _ => continue,
+            };
+            let field_name = name.value.trim();
+            if field_name.is_empty() {
+                if value.value.is_empty() {
+                    continue;
+                }
+                return Err("Custom fields need a name".into());
+            }
+            kind.validate(&value.value)
+                .map_err(|e| format!("Field '{}' {}", field_name, e))?;
+            fields.push(CustomField {
+                name: field_name.to_string(),
+                kind,
+                value: value.value.clone(),
+            });
+        }
+        Ok(fields)
+    }
 }
 
 pub fn render_modal(f: &mut Frame, modal: &Modal, area: Rect) {
@@ -159,6 +473,8 @@ pub fn render_modal(f: &mut Frame, modal: &Modal, area: Rect) {
     match &modal.typ {
         ModalType::Confirm(_) => render_confirmation_modal(f, modal, modal_area),
         ModalType::Input(_) => render_input_modal(f, modal, modal_area),
+        ModalType::History => render_history_modal(f, modal, modal_area),
+        ModalType::Trash => render_trash_modal(f, modal, modal_area),
     }
 }
 
@@ -181,22 +497,28 @@ fn render_confirmation_modal(f: &mut Frame, modal: &Modal, area: Rect) {
 }
 
 fn render_input_modal(f: &mut Frame, modal: &Modal, area: Rect) {
-    let items: Vec<ListItem> = modal
+    let mut items: Vec<ListItem> = modal
         .input_fields
         .iter()
         .enumerate()
         .map(|(i, field)| {
             let is_active = i == modal.active_field;
-            let value = if field.is_password && !is_active && !field.value.is_empty() {
-                "•".repeat(field.value.len())
+            // Master password fields stay masked even while typing
+            let always_masked = modal.typ == ModalType::Input(InputType::ChangePassword);
+            let value = if
+                field.is_password &&
+                (!is_active || always_masked) &&
+                !field.value.is_empty()
+            {
+                Cow::Owned("•".repeat(field.value.len()))
             } else {
-                field.value.clone()
+                Cow::Borrowed(field.value.expose())
             };
 
             let line = Line::from(vec![
                 Span::raw(format!("{}: ", field.label)),
                 Span::styled(
-                    value.clone(),
+                    value,
                     Style::default().fg(if is_active {
                         Color::Yellow
                     } else {
@@ -215,7 +537,15 @@ fn render_input_modal(f: &mut Frame, modal: &Modal, area: Rect) {
         })
         .collect();
 
-    let help_text = "\nTab: Next field | Enter: Confirm | Esc: Cancel";
+    if let Some(strength) = modal.password_strength() {
+        items.push(strength_meter(&strength));
+    }
+
+    let help_text = if modal.has_custom_fields() {
+        "\nTab: Next field | Enter: Confirm | Esc: Cancel | Alt+k: Change kind | Alt+g: Generate password | Alt+a/x/t: Add/remove field, change type"
+    } else {
+        "\nTab: Next field | Enter: Confirm | Esc: Cancel"
+    };
 
     let list = List::new(items).block(
         Block::default()
@@ -225,9 +555,115 @@ fn render_input_modal(f: &mut Frame, modal: &Modal, area: Rect) {
             .border_style(Style::default().fg(match modal.typ {
                 ModalType::Input(InputType::Edit) => Color::Yellow,
                 ModalType::Input(InputType::Create) => Color::Green,
+                ModalType::Input(InputType::ChangePassword) => Color::Magenta,
+                ModalType::Input(InputType::RenameFolder) => Color::Blue,
                 _ => Color::White,
             }))
-            .title(format!("{}{}", modal.title, help_text)),
+            .title(if modal.has_custom_fields() {
+                format!("{} ({}){}", modal.title, modal.kind.label(), help_text)
+            } else {
+                format!("{}{}", modal.title, help_text)
+            }),
+    );
+
+    f.render_widget(list, area);
+}
+
+// a bar of five blocks coloured by score, with the first hint for a weak password:
+fn strength_meter(strength: &Strength) -> ListItem<'static> {
+    let filled = strength.score as usize + 1;
+    let color = match strength.score {
+        0 | 1 => Color::Red,
+        2 => Color::Yellow,
+        _ => Color::Green,
+    };
+    let mut spans = vec![
+        Span::raw("Strength: "),
+        Span::styled("■".repeat(filled), Style::default().fg(color)),
+        Span::styled("□".repeat(5 - filled), Style::default().fg(Color::DarkGray)),
+        Span::styled(format!(" {}", strength.label()), Style::default().fg(color)),
+    ];
+    if strength.score < SETTINGS.password_min_strength {
+        let verdict = if SETTINGS.password_strength_warn_only { " (weak, saved with a warning)" } else { " (too weak to save)" };
+        spans.push(Span::styled(verdict, Style::default().fg(color)));
+    }
+    if let Some(hint) = strength.feedback.first() {
+        spans.push(Span::styled(format!(" - {}", hint), Style::default().fg(Color::Gray)));
+    }
+    ListItem::new(Line::from(spans))
+}
+
+fn render_history_modal(f: &mut Frame, modal: &Modal, area: Rect) {
+    let history = modal.entry.as_ref().map_or(&[][..], |entry| &entry.history);
+    let items: Vec<ListItem> = if history.is_empty() {
+        vec![ListItem::new("No earlier passwords")]
+    } else {
+        history
+            .iter()
+            .enumerate()
+            .map(|(i, old)| {
+                let is_active = i == modal.active_field;
+                // Only the value under the cursor is shown
+                let value = if is_active {
+                    Cow::Borrowed(old.value.expose())
+                } else {
+                    Cow::Borrowed("••••••••")
+                };
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{}. replaced {}: ", i + 1, Timestamps::display(&old.replaced))),
+                    Span::styled(
+                        value,
+                        Style::default().fg(if is_active { Color::Yellow } else { Color::White }),
+                    ),
+                ]))
+            })
+            .collect()
+    };
+
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Cyan))
+            .title(format!(
+                "{}\n↑/↓: Select | Alt+c: Copy | Enter: Restore | Esc: Close",
+                modal.title
+            )),
+    );
+
+    f.render_widget(list, area);
+}
+
+fn render_trash_modal(f: &mut Frame, modal: &Modal, area: Rect) {
+    let items: Vec<ListItem> = if modal.entries.is_empty() {
+        vec![ListItem::new("The trash is empty")]
+    } else {
+        modal
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let is_active = i == modal.active_field;
+                ListItem::new(format!(
+                    "{}{} | deleted {}",
+                    if is_active { "> " } else { "  " },
+                    entry.name,
+                    Timestamps::display(&entry.times.deleted)
+                ))
+                .style(Style::default().fg(if is_active { Color::Yellow } else { Color::White }))
+            })
+            .collect()
+    };
+
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Red))
+            .title(format!(
+                "{}\n↑/↓: Select | Enter: Restore | Alt+d: Delete for good | Alt+x: Empty | Esc: Close",
+                modal.title
+            )),
     );
 
     f.render_widget(list, area);
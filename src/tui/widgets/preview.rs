@@ -1,4 +1,8 @@
+use crate::config::settings::SETTINGS;
+use crate::models::kinds::EntryKind;
+use crate::models::structs::Timestamps;
 use crate::tui::app::App;
+use zeroize::Zeroizing;
 use ratatui::{
     layout::Rect,
     widgets::{Block, BorderType, Borders, Clear, Paragraph},
@@ -9,20 +13,72 @@ pub fn render_preview(f: &mut Frame, app: &App, area: Rect) {
     // Clear the area before rendering the preview
     f.render_widget(Clear, area);
 
-    let details = if let Some(selected) = app.selected_password() {
-        format!(
-            "Name: {}\nID: {}\nPassword: {}\nURL: {}\nNotes: {}",
-            selected.name,
-            selected.id,
-            selected.password,
-            selected.metadata.url.as_deref().unwrap_or("N/A"),
-            selected.metadata.notes.as_deref().unwrap_or("None")
-        )
+    // The preview shows the password in clear, so wipe the text after drawing
+    let details = Zeroizing::new(if let Some(selected) = app.selected_password() {
+        let mut details = match selected.kind {
+            EntryKind::Login => format!(
+                "Name: {}\nUsername: {}\nPassword: {}\nURL: {}\nNotes: {}",
+                selected.name,
+                selected.username,
+                selected.password.expose(),
+                selected.metadata.url.as_deref().unwrap_or("N/A"),
+                selected.metadata.notes.as_deref().unwrap_or("None")
+            ),
+            kind => {
+                let mut details = format!("Name: {}\nKind: {}", selected.name, kind.label());
+                // Secret values are masked like hidden custom fields below
+                for field in kind.fields() {
+                    let value = match selected.detail(field.key) {
+                        "" => "N/A",
+                        _ if field.kind.is_secret() => "••••••••",
+                        value => value,
+                    };
+                    details.push_str(&format!("\n{}: {}", field.label, value));
+                }
+                let notes = selected.metadata.notes.as_deref().unwrap_or("None");
+                if kind == EntryKind::SecureNote {
+                    details.push_str(&format!("\n\n{}", notes));
+                } else {
+                    details.push_str(&format!("\nNotes: {}", notes));
+                }
+                details
+            }
+        };
+        if !selected.folder.is_empty() {
+            details.push_str(&format!("\nFolder: {}", selected.folder));
+        }
+        if !selected.tags.is_empty() {
+            details.push_str(&format!("\nTags: {}", selected.tags.join(", ")));
+        }
+        // Hidden values stay masked (edit the entry to see them), without giving away their length
+        for field in &selected.metadata.fields {
+            let value = if field.kind.is_secret() { "••••••••" } else { field.value.expose() };
+            details.push_str(&format!("\n{} ({}): {}", field.name, field.kind.as_str(), value));
+        }
+        let times = &selected.times;
+        details.push_str(&format!(
+            "\n\nCreated: {}\nModified: {}",
+            Timestamps::display(&times.created),
+            Timestamps::display(&times.modified)
+        ));
+        if selected.primary_secret().is_some() {
+            let label = if selected.kind == EntryKind::Login { "Password" } else { "Secret" };
+            let mut changed = Timestamps::display(&times.password_changed);
+            if let Some(days) = selected.password_age_days() {
+                changed.push_str(&format!(" ({} days ago)", days));
+            }
+            if selected.is_password_stale(SETTINGS.password_max_age_days) {
+                changed.push_str(" - due for rotation");
+            }
+            details.push_str(&format!("\n{} changed: {}", label, changed));
+        }
+        details.push_str(&format!("\nLast used: {}", Timestamps::display(&times.last_used)));
+        details
     } else {
         "No password selected".to_string()
-    };
+    });
 
-    let preview = Paragraph::new(details).block(
+    let preview = Paragraph::new(details.as_str()).block(
         Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
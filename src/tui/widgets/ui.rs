@@ -1,4 +1,5 @@
 use super::{
+    folders::render_folder_tree,
     help::render_help_panel,
     layout::centered_rect,
     list::render_password_list,
@@ -20,28 +21,30 @@ pub fn render_ui(f: &mut Frame, app: &App) {
         .constraints([Constraint::Percentage(100)].as_ref())
         .split(f.area())[0];
 
-    // Split the screen horizontally: left pane for search & passwords, right pane for preview
+    // Split the screen horizontally: folder tree, search & passwords, then the preview
     let main_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
-            Constraint::Percentage(50), // left pane (search box + passwords list)
-            Constraint::Percentage(50), // preview pane (full height)
+            Constraint::Percentage(20), // folder tree (full height)
+            Constraint::Percentage(40), // middle pane (search box + passwords list)
+            Constraint::Percentage(40), // preview pane (full height)
         ])
         .split(padded_area);
 
-    // In the left pane, split vertically for search box and passwords list
+    // In the middle pane, split vertically for search box and passwords list
     let left_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3), // search box at the top
             Constraint::Min(0), // remaining area for passwords
         ])
-        .split(main_chunks[0]);
+        .split(main_chunks[1]);
 
-    // Render left pane widgets
+    // Render the panes
+    render_folder_tree(f, app, main_chunks[0]);
     render_search_box(f, app, left_chunks[0]);
     render_password_list(f, app, left_chunks[1]);
-    render_preview(f, app, main_chunks[1]);
+    render_preview(f, app, main_chunks[2]);
 
     // Overlay help panel if enabled (centers itself over the whole area)
     if app.show_help {
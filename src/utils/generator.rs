@@ -0,0 +1,206 @@
+use rand::Rng;
+use zeroize::Zeroizing;
+
+use crate::models::secret::SecretString;
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!@#$%^&*()-_=+[]{};:,.<>/?~|";
+
+// Characters easily mistaken for one another when read or typed from paper
+const AMBIGUOUS: &str = "Il1|O0o`'\";:,.";
+
+const VOWELS: &str = "aeiou";
+const CONSONANTS: &str = "bcdfghjklmnprstvwz";
+
+// One word per line, lowercase; about 10 bits of entropy per word
+const WORDLIST: &str = include_str!("wordlist.txt");
+
+lazy_static::lazy_static! {
+    pub(crate) static ref WORDS: Vec<&'static str> = WORDLIST.lines().filter(|w| !w.is_empty()).collect();
+}
+
+/// Character-class passwords. Every enabled class appears at least once.
+#[derive(Debug, Clone)]
+pub struct PasswordOptions {
+    pub length: usize,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    /// Leave out characters such as `l`, `1`, `O` and `0`.
+    pub exclude_ambiguous: bool,
+    /// Alternate consonants and vowels, so the password can be read out loud.
+    pub pronounceable: bool,
+}
+
+impl Default for PasswordOptions {
+    fn default() -> Self {
+        Self {
+            length: 20,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: true,
+            exclude_ambiguous: false,
+            pronounceable: false,
+        }
+    }
+}
+
+/// Passphrases made of words from the embedded wordlist.
+#[derive(Debug, Clone)]
+pub struct PassphraseOptions {
+    pub words: usize,
+    pub separator: String,
+    /// Start every word with a capital letter.
+    pub capitalize: bool,
+}
+
+impl Default for PassphraseOptions {
+    fn default() -> Self {
+        Self {
+            words: 5,
+            separator: "-".to_string(),
+            capitalize: false,
+        }
+    }
+}
+
+/// What `rsp generate`, `rsp add --generate` and the TUI generate.
+#[derive(Debug, Clone)]
+pub enum GeneratorOptions {
+    Password(PasswordOptions),
+    Passphrase(PassphraseOptions),
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions::Password(PasswordOptions::default())
+    }
+}
+
+impl GeneratorOptions {
+    pub fn generate(&self) -> Result<SecretString, String> {
+        match self {
+            GeneratorOptions::Password(options) => generate_password(options),
+            GeneratorOptions::Passphrase(options) => generate_passphrase(options),
+        }
+    }
+}
+
+// the characters of a class, without the ambiguous ones if asked to:
+fn class_chars(class: &str, exclude_ambiguous: bool) -> Vec<char> {
+    class
+        .chars()
+        .filter(|c| !exclude_ambiguous || !AMBIGUOUS.contains(*c))
+        .collect()
+}
+
+fn pick<R: Rng>(rng: &mut R, chars: &[char]) -> char {
+    chars[rng.random_range(0..chars.len())]
+}
+
+// Fisher-Yates, so required characters don't always sit at the front
+fn shuffle<R: Rng>(rng: &mut R, chars: &mut [char]) {
+    for i in (1..chars.len()).rev() {
+        chars.swap(i, rng.random_range(0..=i));
+    }
+}
+
+pub fn generate_password(options: &PasswordOptions) -> Result<SecretString, String> {
+    let classes: Vec<Vec<char>> = [
+        (options.lowercase, LOWERCASE),
+        (options.uppercase, UPPERCASE),
+        (options.digits, DIGITS),
+        (options.symbols, SYMBOLS),
+    ]
+    .into_iter()
+    .filter(|(enabled, _)| *enabled)
+    .map(|(_, class)| class_chars(class, options.exclude_ambiguous))
+    .collect();
+
+    if classes.is_empty() {
+        return Err("Enable at least one character class".into());
+    }
+    if options.length < classes.len() {
+        return Err(format!(
+            "A password with {} character classes needs at least {} characters",
+            classes.len(),
+            classes.len()
+        ));
+    }
+    if options.pronounceable {
+        return generate_pronounceable(options);
+    }
+
+    let mut rng = rand::rng();
+    let all: Vec<char> = classes.concat();
+    // Working buffers are wiped like the password itself
+    let mut chars: Zeroizing<Vec<char>> =
+        Zeroizing::new(classes.iter().map(|class| pick(&mut rng, class)).collect());
+    while chars.len() < options.length {
+        chars.push(pick(&mut rng, &all));
+    }
+    shuffle(&mut rng, &mut chars);
+    Ok(chars.iter().collect::<String>().into())
+}
+
+// Syllables of a consonant and a vowel. Uppercase turns one letter into a capital;
+// digits and symbols each take one position at random
+fn generate_pronounceable(options: &PasswordOptions) -> Result<SecretString, String> {
+    let mut rng = rand::rng();
+    let consonants = class_chars(CONSONANTS, options.exclude_ambiguous);
+    let vowels = class_chars(VOWELS, options.exclude_ambiguous);
+
+    let extras: Vec<Vec<char>> = [(options.digits, DIGITS), (options.symbols, SYMBOLS)]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, class)| class_chars(class, options.exclude_ambiguous))
+        .collect();
+    let letters = options.length - extras.len();
+    if letters == 0 || !(options.lowercase || options.uppercase) {
+        return Err("A pronounceable password needs room for letters".into());
+    }
+
+    let mut chars: Zeroizing<Vec<char>> = Zeroizing::new(
+        (0..letters)
+            .map(|i| pick(&mut rng, if i % 2 == 0 { &consonants } else { &vowels }))
+            .collect(),
+    );
+    if options.uppercase {
+        let i = rng.random_range(0..chars.len());
+        chars[i] = chars[i].to_ascii_uppercase();
+    }
+    if !options.lowercase {
+        chars.iter_mut().for_each(|c| *c = c.to_ascii_uppercase());
+    }
+    for class in &extras {
+        let at = rng.random_range(0..=chars.len());
+        chars.insert(at, pick(&mut rng, class));
+    }
+    Ok(chars.iter().collect::<String>().into())
+}
+
+pub fn generate_passphrase(options: &PassphraseOptions) -> Result<SecretString, String> {
+    if options.words == 0 {
+        return Err("A passphrase needs at least one word".into());
+    }
+    let mut rng = rand::rng();
+    let words: Zeroizing<Vec<String>> = Zeroizing::new((0..options.words)
+        .map(|_| {
+            let word = WORDS[rng.random_range(0..WORDS.len())];
+            if options.capitalize {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            } else {
+                word.to_string()
+            }
+        })
+        .collect());
+    Ok(words.join(&options.separator).into())
+}
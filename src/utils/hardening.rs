@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::config::settings::SETTINGS;
+
+lazy_static::lazy_static! {
+    // mlock is not reference counted, so track how many secrets share each locked page
+    static ref LOCKED_PAGES: Mutex<HashMap<usize, usize>> = Mutex::new(HashMap::new());
+}
+
+// Only warn once when the locked memory limit is reached
+static LOCK_WARNED: AtomicBool = AtomicBool::new(false);
+
+fn page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+// start and end of the pages covering `len` bytes at `ptr`:
+fn page_range(ptr: *const u8, len: usize) -> (usize, usize) {
+    let page = page_size();
+    let start = ptr as usize / page * page;
+    let end = (ptr as usize + len).div_ceil(page) * page;
+    (start, end)
+}
+
+// Keep crashes from writing core dumps and same-user processes from attaching with ptrace
+fn disable_core_dumps() -> io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    if unsafe { libc::setrlimit(libc::RLIMIT_CORE, &limit) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn disable_dumpable() -> io::Result<()> {
+    if unsafe { libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// Check that a page can be locked, so secrets will not be swapped out
+fn probe_mlock() -> io::Result<()> {
+    let page = vec![0u8; page_size()];
+    if unsafe { libc::mlock(page.as_ptr() as *const libc::c_void, page.len()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    unsafe {
+        libc::munlock(page.as_ptr() as *const libc::c_void, page.len());
+    }
+    Ok(())
+}
+
+// Apply every hardening step, reporting all the ones that failed
+pub fn harden_process() -> io::Result<()> {
+    let results = [
+        ("PR_SET_DUMPABLE", disable_dumpable()),
+        ("RLIMIT_CORE", disable_core_dumps()),
+        ("mlock", probe_mlock()),
+    ];
+
+    let failures: Vec<String> = results
+        .iter()
+        .filter_map(|(name, result)| result.as_ref().err().map(|e| format!("{}: {}", name, e)))
+        .collect();
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(io::Error::new(io::ErrorKind::Other, failures.join("; ")))
+    }
+}
+
+// Harden the process at startup; refuse to run if that fails and `require_hardening` is set
+pub fn init_hardening() -> io::Result<()> {
+    match harden_process() {
+        Ok(()) => {
+            log::debug!("Process hardening applied");
+            Ok(())
+        }
+        Err(e) => {
+            log::error!("Process hardening failed: {}", e);
+            if SETTINGS.require_hardening {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!("Refusing to run: process hardening failed ({})", e),
+                ));
+            }
+            eprintln!("Warning: process hardening failed ({}). Secrets may end up in swap or core dumps.", e);
+            Ok(())
+        }
+    }
+}
+
+// Lock the pages holding a secret into RAM
+pub fn lock_memory(ptr: *const u8, len: usize) {
+    if len == 0 {
+        return;
+    }
+    let page = page_size();
+    let (start, end) = page_range(ptr, len);
+
+    let mut pages = match LOCKED_PAGES.lock() {
+        Ok(pages) => pages,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    if unsafe { libc::mlock(start as *const libc::c_void, end - start) } != 0
+        && !LOCK_WARNED.swap(true, Ordering::Relaxed)
+    {
+        log::warn!(
+            "Failed to lock secret memory: {}. Secrets may be swapped out.",
+            io::Error::last_os_error()
+        );
+    }
+    // Counted even if mlock failed, so unlocking stays balanced
+    for addr in (start..end).step_by(page) {
+        *pages.entry(addr).or_insert(0) += 1;
+    }
+}
+
+// Release the pages of a secret that was wiped, once no other secret uses them
+pub fn unlock_memory(ptr: *const u8, len: usize) {
+    if len == 0 {
+        return;
+    }
+    let page = page_size();
+    let (start, end) = page_range(ptr, len);
+
+    let mut pages = match LOCKED_PAGES.lock() {
+        Ok(pages) => pages,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    for addr in (start..end).step_by(page) {
+        if let Some(count) = pages.get_mut(&addr) {
+            *count -= 1;
+            if *count == 0 {
+                pages.remove(&addr);
+                unsafe {
+                    libc::munlock(addr as *const libc::c_void, page);
+                }
+            }
+        }
+    }
+}
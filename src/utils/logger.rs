@@ -1,28 +1,339 @@
-use log::debug;
-use std::fs::File;
-use std::io::Write;
-
-// logger function initialization for debugging:
-pub fn init_logger() {
-    use std::env;
-    env::set_var("RUST_LOG", "debug");
-
-    let log_file = File::create("debug.log").expect("Failed to create log file");
-
-    env_logger::Builder::new()
-        .format(|buf, record| {
-            let level = format!("{:5}", record.level()); // Pad to align levels
-            writeln!(
-                buf,
-                "[{}] [{}] {}",
-                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                level,
-                record.args()
-            )
+use log::{LevelFilter, Log, Metadata, Record};
+use std::borrow::Cow;
+use std::fs::{self, File, OpenOptions, Permissions};
+use std::io::{self, Write};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+use crate::config::settings::SETTINGS;
+use crate::data::vault::get_state_dir;
+
+/// Log file of the CLI and the TUI.
+pub const LOG_FILE_NAME: &str = "rustpass.log";
+
+/// Log file of the background daemon.
+pub const DAEMON_LOG_FILE_NAME: &str = "daemon.log";
+
+/// Environment variable overriding the configured log level.
+pub const LOG_ENV_VAR: &str = "RSP_LOG";
+
+const REDACTED: &str = "[REDACTED]";
+
+// Fields whose values never reach the log, also as a suffix (`master_password`, `encryption_key`)
+const SECRET_FIELDS: [&str; 3] = ["password", "key", "notes"];
+
+// Log file that is rotated once it grows past `max_bytes`
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl LogFile {
+    fn open(path: &Path) -> io::Result<Self> {
+        let file = open_log_file(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            size,
         })
-        .target(env_logger::Target::Pipe(Box::new(log_file)))
-        .filter(None, log::LevelFilter::Debug)
-        .init();
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let max_bytes = SETTINGS.log_max_bytes;
+        if max_bytes > 0 && self.size > 0 && self.size + line.len() as u64 > max_bytes {
+            rotate_log_files(&self.path, SETTINGS.log_keep_files)?;
+            self.file = open_log_file(&self.path)?;
+            self.size = 0;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+struct Logger {
+    level: LevelFilter,
+    file: Mutex<Option<LogFile>>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        // The unredacted message is wiped once the line is written
+        let message = Zeroizing::new(record.args().to_string());
+        let line = format!(
+            "[{}] [{:5}] {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            record.level(),
+            redact(&message)
+        );
+
+        if let Ok(mut file) = self.file.lock() {
+            if let Some(file) = file.as_mut() {
+                let _ = file.write_line(&line);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            if let Some(file) = file.as_mut() {
+                let _ = file.file.flush();
+            }
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref LOGGER: Logger = Logger {
+        level: log_level(),
+        file: Mutex::new(None),
+    };
+}
+
+// level from `RSP_LOG`, then `log_level` in the config file:
+fn log_level() -> LevelFilter {
+    let configured = std::env::var(LOG_ENV_VAR).unwrap_or_else(|_| SETTINGS.log_level.clone());
+    LevelFilter::from_str(configured.trim()).unwrap_or_else(|_| {
+        eprintln!("Warning: unknown log level '{}', using 'info'", configured);
+        LevelFilter::Info
+    })
+}
+
+// Path of a log file in the state directory
+pub fn get_log_path(file_name: &str) -> PathBuf {
+    get_state_dir().join(file_name)
+}
+
+// open a log file for appending, owner-only even if it already existed:
+pub fn open_log_file(path: &Path) -> io::Result<File> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .open(path)?;
+    file.set_permissions(Permissions::from_mode(0o600))?;
+    Ok(file)
+}
+
+// shift `log`, `log.1`, ... up by one, dropping everything past `keep` old files:
+pub fn rotate_log_files(path: &Path, keep: usize) -> io::Result<()> {
+    let numbered = |n: usize| PathBuf::from(format!("{}.{}", path.display(), n));
+
+    if keep == 0 {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+
+    let _ = fs::remove_file(numbered(keep));
+    for n in (1..keep).rev() {
+        let from = numbered(n);
+        if from.exists() {
+            fs::rename(&from, numbered(n + 1))?;
+        }
+    }
+    if path.exists() {
+        fs::rename(path, numbered(1))?;
+    }
+    Ok(())
+}
+
+// logger function initialization; everything goes to `file_name` in the state directory:
+pub fn init_logger(file_name: &str) {
+    set_log_file(file_name);
+    if log::set_logger(&*LOGGER).is_ok() {
+        log::set_max_level(LOGGER.level);
+    }
+    log::debug!("Logger initialized");
+}
+
+// switch to another log file, e.g. once the daemon has detached:
+pub fn set_log_file(file_name: &str) {
+    if LOGGER.level == LevelFilter::Off {
+        return;
+    }
+
+    let path = get_log_path(file_name);
+    let file = match LogFile::open(&path) {
+        Ok(file) => Some(file),
+        Err(e) => {
+            eprintln!("Warning: cannot open log file {:?}: {}", path, e);
+            None
+        }
+    };
+    if let Ok(mut current) = LOGGER.file.lock() {
+        *current = file;
+    }
+}
+
+// Replace the values of secret fields (`password: "..."`, `key=...`, `password: ...`)
+// with a placeholder, whatever their quoting. Secret types already print themselves
+// redacted (`SecretString([REDACTED])`) and are left as they are; this catches plain
+// strings and bytes.
+pub fn redact(message: &str) -> Cow<'_, str> {
+    let bytes = message.as_bytes();
+    let mut output = String::new();
+    let mut copied = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if !is_ident_byte(bytes[i]) || (i > 0 && is_ident_byte(bytes[i - 1])) {
+            i += 1;
+            continue;
+        }
+
+        // Whole identifier starting here
+        let ident_start = i;
+        let ident_end = scan_ident(bytes, i);
+        let ident = message[ident_start..ident_end].to_ascii_lowercase();
+        i = ident_end;
+        if !is_secret_field(&ident) {
+            continue;
+        }
+
+        let mut sep = ident_end;
+        // JSON keys: `"key": ...`
+        if ident_start > 0 && bytes[ident_start - 1] == b'"' && bytes.get(sep) == Some(&b'"') {
+            sep += 1;
+        }
+        while sep < bytes.len() && bytes[sep] == b' ' {
+            sep += 1;
+        }
+        let separator = match bytes.get(sep) {
+            Some(&b) if b == b':' || b == b'=' => b,
+            _ => continue,
+        };
+        let mut start = sep + 1;
+        while start < bytes.len() && bytes[start] == b' ' {
+            start += 1;
+        }
+
+        let end = match value_end(bytes, start) {
+            Some(end) => end,
+            // `key=value` pairs are redacted even when the value is a bare word
+            None if separator == b'=' => scan_bare(bytes, start),
+            // After `password: ` an unquoted value may hold spaces, so the rest of the
+            // line goes
+            None => scan_line(bytes, start),
+        };
+        if end == start || message[start..end].contains(REDACTED) {
+            continue;
+        }
+
+        output.push_str(&message[copied..start]);
+        output.push_str(REDACTED);
+        copied = end;
+        i = end;
+    }
+
+    if copied == 0 {
+        return Cow::Borrowed(message);
+    }
+    output.push_str(&message[copied..]);
+    Cow::Owned(output)
+}
+
+fn is_secret_field(ident: &str) -> bool {
+    SECRET_FIELDS
+        .iter()
+        .any(|field| ident == *field || ident.ends_with(&format!("_{}", field)))
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+fn scan_ident(bytes: &[u8], start: usize) -> usize {
+    let mut end = start;
+    while end < bytes.len() && is_ident_byte(bytes[end]) {
+        end += 1;
+    }
+    end
+}
+
+// A bare `key=value` word ends at whitespace or punctuation closing the surrounding text
+fn scan_bare(bytes: &[u8], start: usize) -> usize {
+    let mut end = start;
+    while end < bytes.len() && !b" \t\n,;&)]}".contains(&bytes[end]) {
+        end += 1;
+    }
+    end
+}
+
+fn scan_line(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(bytes.len(), |len| start + len)
+}
+
+// end of a Debug-formatted value: a string, a number, brackets, or `Some(..)`/`Type { .. }`:
+fn value_end(bytes: &[u8], start: usize) -> Option<usize> {
+    match *bytes.get(start)? {
+        b'"' | b'\'' => Some(scan_quoted(bytes, start)),
+        b'[' | b'{' | b'(' => Some(scan_brackets(bytes, start)),
+        b if b.is_ascii_digit() || b == b'-' => Some(scan_ident(bytes, start + 1)),
+        b if is_ident_byte(b) => {
+            let ident_end = scan_ident(bytes, start);
+            match bytes.get(ident_end) {
+                Some(b'(') => Some(scan_brackets(bytes, ident_end)),
+                Some(b' ') if bytes.get(ident_end + 1) == Some(&b'{') => {
+                    Some(scan_brackets(bytes, ident_end + 1))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn scan_quoted(bytes: &[u8], start: usize) -> usize {
+    let quote = bytes[start];
+    let mut end = start + 1;
+    while end < bytes.len() {
+        match bytes[end] {
+            b'\\' => end += 2,
+            b if b == quote => return end + 1,
+            _ => end += 1,
+        }
+    }
+    bytes.len()
+}
 
-    debug!("Logger initialized");
+// Matching close bracket, skipping over quoted strings; the rest of the message if unbalanced
+fn scan_brackets(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0usize;
+    let mut end = start;
+    while end < bytes.len() {
+        match bytes[end] {
+            b'"' | b'\'' => {
+                end = scan_quoted(bytes, end);
+                continue;
+            }
+            b'[' | b'{' | b'(' => depth += 1,
+            b']' | b'}' | b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return end + 1;
+                }
+            }
+            _ => {}
+        }
+        end += 1;
+    }
+    bytes.len()
 }
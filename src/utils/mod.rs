@@ -1,3 +1,7 @@
+pub mod atomic_file;
 pub mod fuzzy_finder;
+pub mod generator;
+pub mod hardening;
 pub mod logger;
-pub mod verify_passwords;
+pub mod strength;
+pub mod verify_passwords;
\ No newline at end of file
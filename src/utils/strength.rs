@@ -0,0 +1,374 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::utils::generator::WORDS;
+
+// Most common passwords first; the rank decides how quickly one is guessed
+const COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+// Rows of a US keyboard, unshifted and shifted
+const KEYBOARD_ROWS: [&str; 8] = [
+    "`1234567890-=",
+    "qwertyuiop[]\\",
+    "asdfghjkl;'",
+    "zxcvbnm,./",
+    "~!@#$%^&*()_+",
+    "QWERTYUIOP{}|",
+    "ASDFGHJKL:\"",
+    "ZXCVBNM<>?",
+];
+
+// Longest word looked up in the dictionaries
+const MAX_WORD: usize = 16;
+
+lazy_static::lazy_static! {
+    static ref COMMON: HashMap<&'static str, usize> = COMMON_PASSWORDS
+        .lines()
+        .filter(|w| !w.is_empty())
+        .enumerate()
+        .map(|(i, w)| (w, i + 1))
+        .collect();
+    static ref DICTIONARY: HashSet<&'static str> = WORDS.iter().copied().collect();
+    static ref KEYS: HashMap<char, (usize, usize)> = KEYBOARD_ROWS
+        .iter()
+        .enumerate()
+        .flat_map(|(row, keys)| keys.chars().enumerate().map(move |(col, key)| (key, (row, col))))
+        .collect();
+}
+
+/// Names of the scores, weakest first.
+pub const SCORE_LABELS: [&str; 5] = ["very weak", "weak", "fair", "strong", "very strong"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pattern {
+    Common,
+    Word,
+    Personal,
+    Keyboard,
+    Sequence,
+    Repeat,
+    Date,
+}
+
+impl Pattern {
+    fn warning(&self) -> &'static str {
+        match self {
+            Pattern::Common => "Contains one of the most common passwords.",
+            Pattern::Word => "Single dictionary words are easy to guess.",
+            Pattern::Personal => "Avoid the entry's name or username.",
+            Pattern::Keyboard => "Keyboard patterns like qwerty are easy to guess.",
+            Pattern::Sequence => "Sequences like abc or 1234 are easy to guess.",
+            Pattern::Repeat => "Repeats like aaa or abcabc are easy to guess.",
+            Pattern::Date => "Dates and years are easy to guess.",
+        }
+    }
+}
+
+// a guessable part of the password, `start..end` in characters
+struct Match {
+    start: usize,
+    end: usize,
+    bits: f64,
+    pattern: Pattern,
+}
+
+/// How hard a password is to guess for someone who knows the usual patterns.
+#[derive(Debug, Clone)]
+pub struct Strength {
+    /// 0 (very weak) to 4 (very strong).
+    pub score: u8,
+    /// Estimated bits of entropy.
+    pub entropy: f64,
+    /// What makes the password guessable and how to improve it; empty for strong ones.
+    pub feedback: Vec<String>,
+}
+
+impl Strength {
+    pub fn label(&self) -> &'static str {
+        SCORE_LABELS[self.score as usize]
+    }
+}
+
+/// Estimate the strength of `password`. `personal` holds values an attacker may know,
+/// like the entry's name and username.
+pub fn estimate(password: &str, personal: &[&str]) -> Strength {
+    let chars: Vec<char> = password.chars().collect();
+    let brute = bruteforce_bits(&chars);
+
+    let mut matches = Vec::new();
+    dictionary_matches(&chars, personal, &mut matches);
+    keyboard_matches(&chars, &mut matches);
+    sequence_matches(&chars, &mut matches);
+    repeat_matches(&chars, brute, &mut matches);
+    date_matches(&chars, &mut matches);
+
+    // The cheapest way to build the password out of matches and characters guessed
+    // one by one: best[i] is the cost of the first i characters and how it ends
+    let mut best: Vec<(f64, Option<usize>)> = vec![(0.0, None); chars.len() + 1];
+    for end in 1..=chars.len() {
+        best[end] = (best[end - 1].0 + brute, None);
+        for (i, m) in matches.iter().enumerate().filter(|(_, m)| m.end == end) {
+            if best[m.start].0 + m.bits < best[end].0 {
+                best[end] = (best[m.start].0 + m.bits, Some(i));
+            }
+        }
+    }
+    let entropy = best[chars.len()].0;
+
+    let mut patterns = Vec::new();
+    let mut end = chars.len();
+    while end > 0 {
+        match best[end].1 {
+            Some(i) => {
+                patterns.insert(0, matches[i].pattern);
+                end = matches[i].start;
+            }
+            None => end -= 1,
+        }
+    }
+
+    let score = match entropy {
+        e if e < 20.0 => 0,
+        e if e < 33.0 => 1,
+        e if e < 50.0 => 2,
+        e if e < 70.0 => 3,
+        _ => 4,
+    };
+
+    let mut feedback: Vec<String> = Vec::new();
+    if score < 3 {
+        for pattern in patterns {
+            let warning = pattern.warning().to_string();
+            if !feedback.contains(&warning) {
+                feedback.push(warning);
+            }
+        }
+        feedback.push(if chars.len() < 12 {
+            "Use a longer password, or a passphrase of several words.".into()
+        } else {
+            "Add a few more characters or words.".into()
+        });
+    }
+
+    Strength { score, entropy, feedback }
+}
+
+type CharClass = fn(&char) -> bool;
+
+// bits per character for guessing blindly from the classes the password uses:
+fn bruteforce_bits(chars: &[char]) -> f64 {
+    let classes: [(CharClass, f64); 5] = [
+        (char::is_ascii_lowercase, 26.0),
+        (char::is_ascii_uppercase, 26.0),
+        (char::is_ascii_digit, 10.0),
+        (char::is_ascii_punctuation, 33.0),
+        (|c: &char| !c.is_ascii() || *c == ' ', 100.0),
+    ];
+    let cardinality: f64 = classes
+        .iter()
+        .filter(|(is_in, _)| chars.iter().any(is_in))
+        .map(|(_, size)| size)
+        .sum();
+    if cardinality > 0.0 { cardinality.log2() } else { 0.0 }
+}
+
+// extra guesses for capitals in a word: one for a capital first letter or all
+// capitals, one per capital otherwise
+fn case_bits(chars: &[char]) -> f64 {
+    let upper = chars.iter().filter(|c| c.is_ascii_uppercase()).count();
+    let letters = chars.iter().filter(|c| c.is_ascii_alphabetic()).count();
+    if upper == 0 {
+        0.0
+    } else if upper == letters || (upper == 1 && chars[0].is_ascii_uppercase()) {
+        1.0
+    } else {
+        upper as f64
+    }
+}
+
+// letters commonly swapped for digits and symbols
+fn unleet(c: char) -> char {
+    match c {
+        '0' => 'o',
+        '1' | '!' => 'i',
+        '3' => 'e',
+        '4' | '@' => 'a',
+        '5' | '$' => 's',
+        '7' => 't',
+        _ => c,
+    }
+}
+
+// common passwords, wordlist words and the personal values, ignoring case and l33t:
+fn dictionary_matches(chars: &[char], personal: &[&str], matches: &mut Vec<Match>) {
+    let personal: HashSet<String> = personal
+        .iter()
+        .flat_map(|value| {
+            let value = value.to_lowercase();
+            let mut parts: Vec<String> = value
+                .split(|c: char| !c.is_alphanumeric())
+                .map(String::from)
+                .collect();
+            parts.push(value);
+            parts
+        })
+        .filter(|part| part.chars().count() >= 3)
+        .collect();
+
+    for start in 0..chars.len() {
+        for end in start + 3..=(start + MAX_WORD).min(chars.len()) {
+            let part = &chars[start..end];
+            let word: String = part.iter().map(|c| c.to_ascii_lowercase()).collect();
+            let plain: String = word.chars().map(unleet).collect();
+
+            // Undoing the substitutions costs a guess more
+            let found = [(&word, 0.0), (&plain, 1.0)].into_iter().find_map(|(candidate, leet)| {
+                let bits = if personal.contains(candidate.as_str()) {
+                    Some((1.0, Pattern::Personal))
+                } else if let Some(rank) = COMMON.get(candidate.as_str()) {
+                    Some(((*rank as f64 + 1.0).log2(), Pattern::Common))
+                } else if DICTIONARY.contains(candidate.as_str()) {
+                    Some(((DICTIONARY.len() as f64).log2(), Pattern::Word))
+                } else {
+                    None
+                };
+                bits.map(|(bits, pattern)| (bits + leet, pattern))
+            });
+            if let Some((bits, pattern)) = found {
+                matches.push(Match { start, end, bits: bits + case_bits(part), pattern });
+            }
+        }
+    }
+    // Personal values can be longer than any dictionary word
+    for value in &personal {
+        let value: Vec<char> = value.chars().collect();
+        if value.len() <= MAX_WORD {
+            continue;
+        }
+        for start in 0..chars.len().saturating_sub(value.len() - 1) {
+            let part = &chars[start..start + value.len()];
+            if part.iter().map(|c| c.to_ascii_lowercase()).eq(value.iter().copied()) {
+                let end = start + value.len();
+                matches.push(Match { start, end, bits: 1.0 + case_bits(part), pattern: Pattern::Personal });
+            }
+        }
+    }
+}
+
+// runs of four or more neighbouring keys along a keyboard row:
+fn keyboard_matches(chars: &[char], matches: &mut Vec<Match>) {
+    for start in 0..chars.len() {
+        let Some(&(row, col)) = KEYS.get(&chars[start]) else {
+            continue;
+        };
+        for step in [1isize, -1] {
+            let mut end = start + 1;
+            let mut col = col as isize;
+            while end < chars.len() && KEYS.get(&chars[end]) == Some(&(row, (col + step) as usize)) {
+                col += step;
+                end += 1;
+            }
+            if end - start >= 4 {
+                // a starting key, a direction and a length
+                let bits = (KEYS.len() as f64).log2() + 1.0 + ((end - start) as f64).log2();
+                matches.push(Match { start, end, bits, pattern: Pattern::Keyboard });
+            }
+        }
+    }
+}
+
+// runs of three or more letters or digits counting up or down, like abc or 9876:
+fn sequence_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let same_class = |a: char, b: char| {
+        (a.is_ascii_lowercase() && b.is_ascii_lowercase())
+            || (a.is_ascii_uppercase() && b.is_ascii_uppercase())
+            || (a.is_ascii_digit() && b.is_ascii_digit())
+    };
+    let mut start = 0;
+    while start + 1 < chars.len() {
+        let step = chars[start + 1] as i32 - chars[start] as i32;
+        let mut end = start + 1;
+        while end < chars.len()
+            && step.abs() == 1
+            && same_class(chars[start], chars[end])
+            && chars[end] as i32 - chars[end - 1] as i32 == step
+        {
+            end += 1;
+        }
+        if end - start >= 3 {
+            let alphabet: f64 = if chars[start].is_ascii_digit() { 10.0 } else { 26.0 };
+            let bits = alphabet.log2() + 1.0 + ((end - start) as f64).log2();
+            matches.push(Match { start, end, bits, pattern: Pattern::Sequence });
+            start = end - 1;
+        } else {
+            start += 1;
+        }
+    }
+}
+
+// a block of up to MAX_WORD characters written several times in a row, like aaa or abcabc:
+fn repeat_matches(chars: &[char], brute: f64, matches: &mut Vec<Match>) {
+    for start in 0..chars.len() {
+        for len in 1..=MAX_WORD.min((chars.len() - start) / 2) {
+            let block = &chars[start..start + len];
+            let times = chars[start..]
+                .chunks_exact(len)
+                .take_while(|chunk| *chunk == block)
+                .count();
+            if times >= 2 && times * len >= 3 {
+                // The block is guessed once; its other copies only cost their count
+                let bits = len as f64 * brute + (times as f64).log2();
+                let end = start + times * len;
+                matches.push(Match { start, end, bits, pattern: Pattern::Repeat });
+            }
+        }
+    }
+}
+
+fn is_date(day: u32, month: u32, year: u32) -> bool {
+    (1..=31).contains(&day) && (1..=12).contains(&month) && (year <= 99 || (1900..=2049).contains(&year))
+}
+
+// years from 1900 to 2049, and dates such as 19900512, 12/05/90 or 1990-05-12:
+fn date_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let year_bits = 150f64.log2();
+    let date_bits = (366.0 * 150f64).log2();
+
+    for start in 0..chars.len() {
+        for end in start + 4..=(start + 10).min(chars.len()) {
+            let text: String = chars[start..end].iter().collect();
+            let groups: Vec<&str> = text.split(['/', '-', '.', '_', ' ']).collect();
+            if groups.iter().any(|g| g.is_empty() || !g.chars().all(|c| c.is_ascii_digit())) {
+                continue;
+            }
+            let number = |s: &str| s.parse::<u32>().unwrap_or(0);
+
+            let bits = match groups.as_slice() {
+                [digits] if digits.len() == 4 && (1900..=2049).contains(&number(digits)) => Some(year_bits),
+                [digits] if digits.len() == 6 || digits.len() == 8 => {
+                    let year_len = digits.len() - 4;
+                    let (a, b) = (number(&digits[..2]), number(&digits[2..4]));
+                    let year_last = number(&digits[4..]);
+                    let (year_first, c, d) = (
+                        number(&digits[..year_len]),
+                        number(&digits[year_len..year_len + 2]),
+                        number(&digits[year_len + 2..]),
+                    );
+                    let found = is_date(a, b, year_last) || is_date(b, a, year_last) || is_date(d, c, year_first);
+                    found.then_some(date_bits)
+                }
+                [a, b, c] if text.chars().filter(|c| !c.is_ascii_digit()).collect::<HashSet<_>>().len() == 1 => {
+                    let short = |s: &str| (1..=2).contains(&s.len());
+                    let year = |s: &str| s.len() == 2 || s.len() == 4;
+                    let found = (short(a) && short(b) && year(c)
+                        && (is_date(number(a), number(b), number(c)) || is_date(number(b), number(a), number(c))))
+                        || (short(c) && short(b) && a.len() == 4 && is_date(number(c), number(b), number(a)));
+                    found.then_some(date_bits + 1.0)
+                }
+                _ => None,
+            };
+            if let Some(bits) = bits {
+                matches.push(Match { start, end, bits, pattern: Pattern::Date });
+            }
+        }
+    }
+}
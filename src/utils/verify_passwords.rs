@@ -1,29 +1,37 @@
 use crate::{
-    models::structs::PasswordEntry,
-    tui::{app::App, widgets::notification::Notification},
+    config::settings::SETTINGS,
+    models::{kinds::EntryKind, structs::PasswordEntry},
+    utils::strength::{estimate, Strength},
 };
-use ratatui::style::Color;
-use std::time::Instant;
 
-// verify whether the password is valid:
-pub fn verify_password(entry: &PasswordEntry, app: &mut App) -> bool {
-    if entry.password.len() > 21 || entry.password.len() < 8 {
-        app.notification = Some(Notification {
-            header: "Error".into(),
-            message: "Password must be between 8 and 21 characters long".to_string(),
-            color: Color::Red,
-            created: Instant::now(),
-        });
-        return false;
+// verify whether the entry can be saved. Login passwords must reach
+// `password_min_strength`; a warning for a weaker one that is saved anyway is returned:
+pub fn verify_password(entry: &PasswordEntry) -> Result<Option<String>, String> {
+    entry.validate()?;
+    if entry.kind != EntryKind::Login {
+        return Ok(None);
     }
-    if entry.name.is_empty() || entry.id.is_empty() {
-        app.notification = Some(Notification {
-            header: "Error".into(),
-            message: "Name and ID cannot be empty".into(),
-            color: Color::Red,
-            created: Instant::now(),
-        });
-        return false;
+    let strength = estimate(entry.password.expose(), &[&entry.name, &entry.username]);
+    check_strength(&strength, SETTINGS.password_min_strength, SETTINGS.password_strength_warn_only)
+}
+
+// compare a strength with the minimum score; below it is an error, or only a warning:
+pub fn check_strength(strength: &Strength, min_score: u8, warn_only: bool) -> Result<Option<String>, String> {
+    if strength.score >= min_score {
+        return Ok(None);
+    }
+    let message = format!(
+        "Password is {} ({}/4, at least {} needed). {}",
+        strength.label(),
+        strength.score,
+        min_score,
+        strength.feedback.join(" ")
+    )
+    .trim_end()
+    .to_string();
+    if warn_only {
+        Ok(Some(message))
+    } else {
+        Err(message)
     }
-    true
 }
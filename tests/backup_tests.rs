@@ -0,0 +1,104 @@
+use std::fs;
+
+use rustpass::auth::keyslots::UnlockSecret;
+use rustpass::data::backup::{
+    backups_with_other_keys, create_backup, find_backup, get_backup_dir, list_backups, restore_backup,
+};
+use rustpass::data::data::{
+    change_master_password, create_password_file, load_passwords, record_use, save_passwords,
+};
+use rustpass::data::revision::last_seen_revision;
+use rustpass::models::structs::PasswordEntry;
+
+mod common;
+use common::temp_vault_path;
+
+#[test]
+fn test_backups_rotate() {
+    let path = temp_vault_path();
+    fs::write(&path, "{}").unwrap();
+
+    for _ in 0..4 {
+        create_backup(&path, 2).unwrap();
+    }
+    assert_eq!(list_backups(&path).unwrap().len(), 2);
+    assert!(create_backup(&path, 0).unwrap().is_none());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_restore_previous_version() {
+    let path = temp_vault_path();
+    let (_, key, mut header) = create_password_file(&path, "correct horse").unwrap();
+
+    let entry = PasswordEntry::new("github".into(), "octocat".into(), "hunter22".into());
+    save_passwords(&path, &[entry], &key, &mut header).unwrap();
+    assert_eq!(load_passwords(&path, "correct horse", None).unwrap().0.len(), 1);
+
+    let revision = last_seen_revision(&path).unwrap();
+
+    // The newest backup is the empty vault written before the entry was added
+    let backup = find_backup(&path, "1").unwrap();
+    assert!(restore_backup(&path, &backup, &UnlockSecret::Password("wrong", None), 5).is_err());
+    let (_, _, restored) =
+        restore_backup(&path, &backup, &UnlockSecret::Password("correct horse", None), 5).unwrap();
+    assert!(load_passwords(&path, "correct horse", None).unwrap().0.is_empty());
+
+    // It is saved as a new revision instead of rewinding the one seen
+    assert_eq!(restored.revision, revision + 1);
+    assert_eq!(last_seen_revision(&path), Some(revision + 1));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_recording_use_saves_without_a_backup() {
+    let path = temp_vault_path();
+    let (_, key, mut header) = create_password_file(&path, "correct horse").unwrap();
+
+    let entry = PasswordEntry::new("github".into(), "octocat".into(), "hunter22".into());
+    let id = entry.id.clone();
+    save_passwords(&path, &[entry], &key, &mut header).unwrap();
+    let backups = list_backups(&path).unwrap().len();
+    let newest = fs::read(&find_backup(&path, "1").unwrap().path).unwrap();
+
+    record_use(&path, &key, &mut header, &id).unwrap();
+    let (passwords, _, _) = load_passwords(&path, "correct horse", None).unwrap();
+    assert!(!passwords[0].times.last_used.is_empty());
+
+    // The newest backup is still the vault from before the entry was added
+    assert_eq!(list_backups(&path).unwrap().len(), backups);
+    assert_eq!(fs::read(&find_backup(&path, "1").unwrap().path).unwrap(), newest);
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_password_change_moves_backups_to_the_new_key() {
+    let path = temp_vault_path();
+    let (_, key, mut header) = create_password_file(&path, "correct horse").unwrap();
+    let entry = PasswordEntry::new("github".into(), "octocat".into(), "hunter22".into());
+    save_passwords(&path, &[entry], &key, &mut header).unwrap();
+
+    // A backup under some earlier key can't be moved
+    let other = path.with_file_name("other.json");
+    create_password_file(&other, "correct horse").unwrap();
+    let stray = get_backup_dir(&path).join("20000101-000000.000000.json");
+    fs::copy(&other, &stray).unwrap();
+
+    let (_, new_key, _) = change_master_password(&path, "correct horse", "battery staple", None, &[]).unwrap();
+
+    let backups = list_backups(&path).unwrap();
+    assert_eq!(backups.len(), 3);
+    for backup in backups.iter().filter(|b| b.path != stray) {
+        assert!(load_passwords(&backup.path, "correct horse", None).is_err());
+        load_passwords(&backup.path, "battery staple", None).unwrap();
+    }
+
+    let exposed = backups_with_other_keys(&path, &new_key).unwrap();
+    assert_eq!(exposed.len(), 1);
+    assert_eq!(exposed[0].path, stray);
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
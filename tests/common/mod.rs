@@ -0,0 +1,44 @@
+// Helpers shared by the integration tests; not every test file uses all of them
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Once;
+
+use rustpass::models::structs::PasswordEntry;
+
+static SCRATCH_DATA_DIR: Once = Once::new();
+
+// point XDG_DATA_HOME at a scratch dir, once per test binary so parallel tests
+// never change it under each other:
+pub fn use_scratch_data_dir() {
+    SCRATCH_DATA_DIR.call_once(|| {
+        std::env::set_var("XDG_DATA_HOME", std::env::temp_dir().join("rustpass-test-data"));
+    });
+}
+
+// fresh vault path in its own scratch directory:
+pub fn temp_vault_path() -> PathBuf {
+    // Revisions the vault records go to the scratch data dir, not the real one
+    use_scratch_data_dir();
+    let dir = std::env::temp_dir().join(format!("rustpass-test-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    dir.join("test.json")
+}
+
+// plain login entry for tests that only care about names:
+pub fn entry(name: &str) -> PasswordEntry {
+    PasswordEntry::new(name.into(), "me".into(), "hunter22".into())
+}
+
+// login entry filed in a folder:
+pub fn entry_in(name: &str, folder: &str) -> PasswordEntry {
+    let mut entry = entry(name);
+    entry.folder = folder.into();
+    entry
+}
+
+// RFC 3339 timestamp the given number of days back:
+pub fn days_ago(days: i64) -> String {
+    (chrono::Utc::now() - chrono::Duration::days(days)).to_rfc3339()
+}
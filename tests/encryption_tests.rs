@@ -1,7 +1,9 @@
 use rustpass::encryption::{
+    cipher::{cipher_for, decrypt_with, encrypt_with},
     decrypt::{decode_codesmith28, fibbil_unhash},
     encrypt::{codesmith28, fibbil_hash},
 };
+use rustpass::models::structs::CipherKind;
 
 #[test]
 fn test_fibbil_encryption_decryption() {
@@ -46,3 +48,25 @@ fn test_codesmith28_invalid_input() {
     assert_eq!(decode_codesmith28("no|separator"), "");
     assert_eq!(decode_codesmith28("a|12"), "");
 }
+
+#[test]
+fn test_ciphers_roundtrip_and_reject_bad_input() {
+    let key = [42u8; 32];
+    for kind in [CipherKind::Aes256Gcm, CipherKind::XChaCha20Poly1305] {
+        let (nonce, ciphertext) = encrypt_with(kind, b"secret entries", b"header", &key).unwrap();
+        assert_eq!(
+            decrypt_with(kind, &ciphertext, b"header", &key, &nonce).unwrap(),
+            b"secret entries"
+        );
+
+        assert!(decrypt_with(kind, &ciphertext, b"header", &[0u8; 32], &nonce).is_err());
+        assert!(decrypt_with(kind, &ciphertext, b"header", &key, &nonce[1..]).is_err());
+        assert!(decrypt_with(kind, &ciphertext, b"tampered", &key, &nonce).is_err());
+
+        // The ciphers themselves return errors for bad nonces too
+        let cipher = cipher_for(kind);
+        assert!(cipher.encrypt(&key, &nonce[1..], b"data", b"").is_err());
+        assert!(cipher.encrypt(&key, &[0u8; 64], b"data", b"").is_err());
+        assert!(cipher.decrypt(&key, &[], &ciphertext, b"header").is_err());
+    }
+}
@@ -0,0 +1,72 @@
+use std::fs;
+
+use rustpass::data::data::{create_password_file, load_passwords, save_passwords, update_passwords};
+use rustpass::data::folders::{list_folders, normalize_folder, parse_tags, rename_folder};
+
+mod common;
+use common::{entry_in, temp_vault_path};
+
+#[test]
+fn test_folders_and_tags_are_normalized() {
+    assert_eq!(normalize_folder(" /work//aws/prod/ "), "work/aws/prod");
+    assert_eq!(normalize_folder("/"), "");
+    assert_eq!(parse_tags("prod, db,,Prod , ops"), vec!["prod", "db", "ops"]);
+}
+
+#[test]
+fn test_list_folders_includes_parents_in_tree_order() {
+    let passwords = vec![
+        entry_in("a", "work/aws/prod"),
+        entry_in("b", "work-old"),
+        entry_in("c", ""),
+        entry_in("d", "work/gcp"),
+    ];
+    assert_eq!(
+        list_folders(&passwords),
+        vec!["work", "work/aws", "work/aws/prod", "work/gcp", "work-old"]
+    );
+}
+
+#[test]
+fn test_rename_folder_moves_subfolders_only() {
+    let mut passwords = vec![
+        entry_in("a", "work/aws/prod"),
+        entry_in("b", "work"),
+        entry_in("c", "work-old"),
+        entry_in("d", "personal"),
+    ];
+
+    assert_eq!(rename_folder(&mut passwords, "work", "archive/2023").unwrap(), 2);
+    assert_eq!(passwords[0].folder, "archive/2023/aws/prod");
+    assert_eq!(passwords[1].folder, "archive/2023");
+    assert_eq!(passwords[2].folder, "work-old");
+
+    // Moving to the top level, into itself, or a folder that doesn't exist
+    assert_eq!(rename_folder(&mut passwords, "archive/2023/aws", "").unwrap(), 1);
+    assert_eq!(passwords[0].folder, "prod");
+    assert!(rename_folder(&mut passwords, "archive", "archive/old").is_err());
+    assert!(rename_folder(&mut passwords, "missing", "elsewhere").is_err());
+}
+
+#[test]
+fn test_rename_folder_is_a_single_save() {
+    let path = temp_vault_path();
+
+    let (_, key, mut header) = create_password_file(&path, "correct horse").unwrap();
+    let passwords = vec![entry_in("a", "work/aws"), entry_in("b", "work"), entry_in("c", "home")];
+    save_passwords(&path, &passwords, &key, &mut header).unwrap();
+    let generation = header.generation;
+
+    let (_, moved) = update_passwords(&path, &key, &mut header, |passwords| {
+        rename_folder(passwords, "work", "clients/acme")
+    })
+    .unwrap();
+    assert_eq!(moved, 2);
+    assert_eq!(header.generation, generation + 1);
+
+    let (passwords, _, _) = load_passwords(&path, "correct horse", None).unwrap();
+    let folders: Vec<&str> = passwords.iter().map(|e| e.folder.as_str()).collect();
+    assert_eq!(folders, vec!["clients/acme/aws", "clients/acme", "home"]);
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
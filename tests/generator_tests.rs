@@ -0,0 +1,96 @@
+use rustpass::utils::generator::{
+    generate_passphrase, generate_password, GeneratorOptions, PassphraseOptions, PasswordOptions,
+};
+
+#[test]
+fn test_passwords_have_the_length_and_every_class() {
+    for _ in 0..50 {
+        let password = generate_password(&PasswordOptions::default()).unwrap();
+        let password = password.expose();
+        assert_eq!(password.chars().count(), 20);
+        assert!(password.chars().any(|c| c.is_ascii_lowercase()), "{}", password);
+        assert!(password.chars().any(|c| c.is_ascii_uppercase()), "{}", password);
+        assert!(password.chars().any(|c| c.is_ascii_digit()), "{}", password);
+        assert!(password.chars().any(|c| !c.is_ascii_alphanumeric()), "{}", password);
+    }
+
+    // Every class shows up even when there is barely room for them
+    let tight = PasswordOptions { length: 4, ..Default::default() };
+    let password = generate_password(&tight).unwrap();
+    assert!(password.expose().chars().any(|c| c.is_ascii_digit()));
+}
+
+#[test]
+fn test_disabled_and_ambiguous_characters_are_left_out() {
+    let options = PasswordOptions {
+        length: 64,
+        symbols: false,
+        uppercase: false,
+        exclude_ambiguous: true,
+        ..Default::default()
+    };
+    for _ in 0..20 {
+        let password = generate_password(&options).unwrap();
+        for c in password.expose().chars() {
+            assert!(c.is_ascii_lowercase() || c.is_ascii_digit(), "{}", c);
+            assert!(!"l1o0".contains(c), "{}", c);
+        }
+    }
+}
+
+#[test]
+fn test_pronounceable_passwords_alternate_consonants_and_vowels() {
+    let options = PasswordOptions {
+        length: 12,
+        uppercase: false,
+        digits: false,
+        symbols: false,
+        pronounceable: true,
+        ..Default::default()
+    };
+    let password = generate_password(&options).unwrap();
+    let letters: Vec<char> = password.expose().chars().collect();
+    assert_eq!(letters.len(), 12);
+    for (i, c) in letters.iter().enumerate() {
+        assert_eq!("aeiou".contains(*c), i % 2 == 1, "{}", password.expose());
+    }
+
+    // Digits and symbols are mixed in without changing the length
+    let mixed = PasswordOptions { pronounceable: true, ..Default::default() };
+    let password = generate_password(&mixed).unwrap();
+    assert_eq!(password.expose().chars().count(), 20);
+    assert!(password.expose().chars().any(|c| c.is_ascii_digit()));
+}
+
+#[test]
+fn test_passphrases_use_words_separator_and_capitals() {
+    let options = PassphraseOptions {
+        words: 6,
+        separator: ".".into(),
+        capitalize: true,
+    };
+    let passphrase = generate_passphrase(&options).unwrap();
+    let words: Vec<&str> = passphrase.expose().split('.').collect();
+    assert_eq!(words.len(), 6);
+    for word in words {
+        assert!(word.chars().next().unwrap().is_ascii_uppercase(), "{}", word);
+        assert!(word.chars().skip(1).all(|c| c.is_ascii_lowercase()), "{}", word);
+    }
+
+    let default = GeneratorOptions::Passphrase(PassphraseOptions::default()).generate().unwrap();
+    assert_eq!(default.expose().split('-').count(), 5);
+}
+
+#[test]
+fn test_impossible_options_are_rejected() {
+    let none = PasswordOptions {
+        lowercase: false,
+        uppercase: false,
+        digits: false,
+        symbols: false,
+        ..Default::default()
+    };
+    assert!(generate_password(&none).is_err());
+    assert!(generate_password(&PasswordOptions { length: 3, ..Default::default() }).is_err());
+    assert!(generate_passphrase(&PassphraseOptions { words: 0, ..Default::default() }).is_err());
+}
@@ -0,0 +1,32 @@
+use rustpass::models::secret::SecretString;
+use rustpass::utils::hardening::harden_process;
+
+#[test]
+fn test_harden_process() {
+    // mlock can legitimately fail under a tight RLIMIT_MEMLOCK; the rest must work
+    if let Err(e) = harden_process() {
+        assert!(!e.to_string().contains("PR_SET_DUMPABLE"), "{}", e);
+        assert!(!e.to_string().contains("RLIMIT_CORE"), "{}", e);
+    }
+
+    assert_eq!(unsafe { libc::prctl(libc::PR_GET_DUMPABLE, 0, 0, 0, 0) }, 0);
+    let mut limit = libc::rlimit {
+        rlim_cur: 1,
+        rlim_max: 1,
+    };
+    assert_eq!(unsafe { libc::getrlimit(libc::RLIMIT_CORE, &mut limit) }, 0);
+    assert_eq!(limit.rlim_cur, 0);
+}
+
+#[test]
+fn test_locked_secrets_survive_growth() {
+    let mut password = SecretString::from("start");
+    let copy = password.clone();
+    for _ in 0..200 {
+        password.push('x');
+    }
+    drop(copy);
+
+    assert_eq!(password.len(), 205);
+    assert!(password.expose().starts_with("startx"));
+}
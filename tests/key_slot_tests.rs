@@ -0,0 +1,94 @@
+use std::fs;
+
+use rustpass::auth::keyfile::generate_key_file;
+use rustpass::auth::keyslots::{create_key_slot, generate_recovery_code, UnlockSecret};
+use rustpass::data::data::{
+    change_master_password, create_password_file, create_vault, load_passwords, load_passwords_with_secret,
+    update_key_slots,
+};
+use rustpass::models::structs::CipherKind;
+
+mod common;
+use common::temp_vault_path;
+
+#[test]
+fn test_recovery_code_and_key_file_unlock_vault() {
+    let path = temp_vault_path();
+    let (_, key, mut header) = create_password_file(&path, "correct horse").unwrap();
+
+    let code = generate_recovery_code();
+    let keyfile = b"some random key file contents".to_vec();
+    let recovery_slot = create_key_slot(&key, &UnlockSecret::RecoveryCode(&code), "recovery").unwrap();
+    let keyfile_slot = create_key_slot(&key, &UnlockSecret::KeyFile(&keyfile), "usb").unwrap();
+    update_key_slots(&path, &key, &mut header, |slots| {
+        slots.push(recovery_slot);
+        slots.push(keyfile_slot);
+        Ok(())
+    })
+    .unwrap();
+
+    // Recovery codes are accepted in lowercase and without dashes
+    let typed = code.replace('-', "").to_lowercase();
+    let (_, unlocked, _) =
+        load_passwords_with_secret(&path, &UnlockSecret::RecoveryCode(&typed)).unwrap();
+    assert_eq!(unlocked, key);
+    let (_, unlocked, _) =
+        load_passwords_with_secret(&path, &UnlockSecret::KeyFile(&keyfile)).unwrap();
+    assert_eq!(unlocked, key);
+    assert!(load_passwords_with_secret(&path, &UnlockSecret::KeyFile(b"other")).is_err());
+
+    // Changing the password re-keys the vault; the other slots need their secrets to move along
+    let err = change_master_password(&path, "correct horse", "battery staple", None, &[]).unwrap_err();
+    assert!(err.contains("recovery") && err.contains("usb"), "{}", err);
+    assert!(load_passwords(&path, "correct horse", None).is_ok());
+
+    let others = [UnlockSecret::KeyFile(&keyfile), UnlockSecret::RecoveryCode(&code)];
+    let (_, new_key, header) =
+        change_master_password(&path, "correct horse", "battery staple", None, &others).unwrap();
+    assert_ne!(new_key, key);
+    assert_eq!(header.key_slots.len(), 3);
+    let (_, unlocked, _) =
+        load_passwords_with_secret(&path, &UnlockSecret::RecoveryCode(&code)).unwrap();
+    assert_eq!(unlocked, new_key);
+    assert!(load_passwords_with_secret(&path, &UnlockSecret::KeyFile(&keyfile)).is_ok());
+    assert!(load_passwords(&path, "battery staple", None).is_ok());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_last_key_slot_cannot_be_removed() {
+    let path = temp_vault_path();
+    let (_, key, mut header) = create_password_file(&path, "correct horse").unwrap();
+
+    let err = update_key_slots(&path, &key, &mut header, |slots| {
+        slots.clear();
+        Ok(())
+    })
+    .unwrap_err();
+    assert!(err.contains("last key slot"), "{}", err);
+    assert!(load_passwords(&path, "correct horse", None).is_ok());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_password_with_key_file_needs_both() {
+    let path = temp_vault_path();
+    let keyfile = b"second factor".to_vec();
+    create_vault(&path, "correct horse", Some(&keyfile), CipherKind::default(), Vec::new()).unwrap();
+
+    let err = load_passwords(&path, "correct horse", None).unwrap_err();
+    assert!(err.contains("requires a key file"), "{}", err);
+    assert!(load_passwords(&path, "correct horse", Some(b"wrong file")).is_err());
+    assert!(load_passwords(&path, "wrong", Some(&keyfile)).is_err());
+    assert!(load_passwords(&path, "correct horse", Some(&keyfile)).is_ok());
+
+    // The generated key file refuses to overwrite an existing file
+    let keyfile_path = path.parent().unwrap().join("test.key");
+    generate_key_file(&keyfile_path).unwrap();
+    assert_eq!(fs::read(&keyfile_path).unwrap().len(), 64);
+    assert!(generate_key_file(&keyfile_path).is_err());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
@@ -0,0 +1,19 @@
+use rustpass::data::vault::set_active_vault;
+use rustpass::models::secret::SecretKey;
+use rustpass::state::keyring::{load_key, revoke_key, store_key, Keyring};
+
+#[test]
+fn test_keyring_key_is_revoked() {
+    // Use a vault name of our own so a real session is left alone
+    set_active_vault(&format!("keyring-test-{}", uuid::Uuid::new_v4())).unwrap();
+    let key = SecretKey::new(&mut [7u8; 32]);
+    // Containers often block keyctl; nothing to test then
+    if let Err(e) = store_key(Keyring::Session, &key, 60) {
+        eprintln!("kernel keyring unavailable: {}", e);
+        return;
+    }
+
+    assert_eq!(load_key(Keyring::Session).unwrap(), Some(key));
+    revoke_key(Keyring::Session).unwrap();
+    assert_eq!(load_key(Keyring::Session).unwrap(), None);
+}
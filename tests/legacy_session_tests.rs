@@ -0,0 +1,33 @@
+use std::fs;
+
+use rustpass::models::secret::SecretKey;
+use rustpass::models::structs::VaultHeader;
+use rustpass::state::key::legacy_session_file_paths;
+use rustpass::state::manager::STATE_MANAGER;
+
+#[test]
+fn test_unlock_and_lock_delete_legacy_session_files() {
+    // Default settings, and no real session, vault or daemon is touched
+    let dir = std::env::temp_dir().join(format!("rustpass-legacy-{}", uuid::Uuid::new_v4()));
+    std::env::set_var("XDG_DATA_HOME", dir.join("data"));
+    std::env::set_var("XDG_CONFIG_HOME", dir.join("config"));
+    std::env::set_var("XDG_RUNTIME_DIR", dir.join("run"));
+
+    let write_legacy_files = || {
+        for path in legacy_session_file_paths() {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "{\"nonce\":\"\",\"encrypted_data\":\"\"}").unwrap();
+        }
+    };
+
+    write_legacy_files();
+    let key = SecretKey::new(&mut [42u8; 32]);
+    STATE_MANAGER.unlock(Vec::new(), key, VaultHeader::new(Vec::new())).unwrap();
+    assert!(legacy_session_file_paths().iter().all(|path| !path.exists()));
+
+    write_legacy_files();
+    STATE_MANAGER.invalidate().unwrap();
+    assert!(legacy_session_file_paths().iter().all(|path| !path.exists()));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
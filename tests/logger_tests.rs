@@ -0,0 +1,56 @@
+use rustpass::utils::logger::{redact, rotate_log_files};
+use std::fs;
+
+#[test]
+fn test_redact_secret_fields() {
+    let cases = vec![
+        (
+            r#"PasswordEntry { name: "mail", password: "hunter2", notes: Some("pin 1234") }"#,
+            r#"PasswordEntry { name: "mail", password: [REDACTED], notes: [REDACTED] }"#,
+        ),
+        (
+            "unlock master_password=hunter2 vault=default",
+            "unlock master_password=[REDACTED] vault=default",
+        ),
+        ("encryption_key: [1, 2, 3], unlocked: true", "encryption_key: [REDACTED], unlocked: true"),
+        (r#"{"key":"c2VjcmV0"}"#, r#"{"key":[REDACTED]}"#),
+        // Unquoted values after a label, like `debug!("password: {}", secret.expose())`
+        ("password: hunter2", "password: [REDACTED]"),
+        ("Unlocking with password: correct horse\nnext line", "Unlocking with password: [REDACTED]\nnext line"),
+        ("Invalid master password: Decryption failed", "Invalid master password: [REDACTED]"),
+    ];
+
+    for (message, expected) in cases {
+        assert_eq!(redact(message), expected);
+    }
+}
+
+#[test]
+fn test_redact_leaves_plain_messages_alone() {
+    for message in [
+        "Migrated vault \"work\" to key slots",
+        "password: SecretString([REDACTED])",
+        "Copying password of mail",
+    ] {
+        assert_eq!(redact(message), message);
+    }
+}
+
+#[test]
+fn test_rotate_log_files() {
+    let dir = std::env::temp_dir().join(format!("rustpass_log_test_{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    let log = dir.join("rustpass.log");
+
+    for round in 0..4 {
+        fs::write(&log, format!("round {}", round)).unwrap();
+        rotate_log_files(&log, 2).unwrap();
+    }
+
+    assert!(!log.exists());
+    assert_eq!(fs::read_to_string(dir.join("rustpass.log.1")).unwrap(), "round 3");
+    assert_eq!(fs::read_to_string(dir.join("rustpass.log.2")).unwrap(), "round 2");
+    assert!(!dir.join("rustpass.log.3").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
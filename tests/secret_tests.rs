@@ -0,0 +1,33 @@
+use rustpass::models::secret::{SecretKey, SecretString};
+
+#[test]
+fn test_secret_debug_is_redacted() {
+    let password = SecretString::from("hunter2");
+    let key = SecretKey::new(&mut [9u8; 32]);
+
+    assert!(!format!("{:?}", password).contains("hunter2"));
+    assert!(!format!("{:?}", key).contains('9'));
+}
+
+#[test]
+fn test_secret_string_editing() {
+    let mut password = SecretString::new();
+    for c in "pässwörd-that-is-longer-than-the-initial-capacity-of-sixty-four-bytes".chars() {
+        password.push(c);
+    }
+    assert_eq!(password.pop(), Some('s'));
+    assert!(password.expose().starts_with("pässwörd"));
+
+    password.clear();
+    assert!(password.is_empty());
+}
+
+#[test]
+fn test_secret_key_wipes_source() {
+    let mut bytes = [5u8; 32];
+    let key = SecretKey::new(&mut bytes);
+
+    assert_eq!(bytes, [0u8; 32]);
+    assert_eq!(key.expose(), &[5u8; 32]);
+    assert!(SecretKey::try_from(vec![1u8; 16]).is_err());
+}
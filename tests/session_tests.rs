@@ -0,0 +1,32 @@
+use std::fs;
+
+use rustpass::models::secret::SecretKey;
+use rustpass::state::key::{delete_key, get_key_file_path, load_key, save_key, SESSION_ENV_VAR};
+
+#[test]
+fn test_session_token_unwraps_key() {
+    // Keep the session file out of the real data directory
+    let dir = std::env::temp_dir().join(format!("rustpass-session-{}", uuid::Uuid::new_v4()));
+    std::env::set_var("XDG_DATA_HOME", &dir);
+    std::env::remove_var(SESSION_ENV_VAR);
+
+    let key = SecretKey::new(&mut [42u8; 32]);
+    let token = save_key(&key).unwrap();
+
+    // Neither the token nor the key is stored in the session file
+    let contents = fs::read_to_string(get_key_file_path()).unwrap();
+    assert!(!contents.contains(&token));
+
+    assert!(load_key().is_err());
+    std::env::set_var(SESSION_ENV_VAR, "not-the-token");
+    assert!(load_key().is_err());
+    std::env::set_var(SESSION_ENV_VAR, &token);
+    assert_eq!(load_key().unwrap(), key);
+
+    // Locking ends the session even if the token is still exported
+    delete_key().unwrap();
+    assert!(load_key().is_err());
+
+    std::env::remove_var(SESSION_ENV_VAR);
+    fs::remove_dir_all(&dir).unwrap();
+}
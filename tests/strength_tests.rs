@@ -0,0 +1,55 @@
+use rustpass::utils::generator::{generate_passphrase, generate_password, PassphraseOptions, PasswordOptions};
+use rustpass::utils::strength::estimate;
+use rustpass::utils::verify_passwords::check_strength;
+
+#[test]
+fn test_common_and_patterned_passwords_are_weak() {
+    for password in ["password1", "Password1!", "qwertyuiop", "12345678", "aaaaaaaaaa", "abcabcabc", "P@ssw0rd", "summer2024"] {
+        let strength = estimate(password, &[]);
+        assert!(strength.score <= 1, "{} scored {} ({:.1} bits)", password, strength.score, strength.entropy);
+        assert!(!strength.feedback.is_empty(), "{}", password);
+    }
+
+    let date = estimate("19900512", &[]);
+    assert!(date.feedback.iter().any(|hint| hint.contains("Dates")), "{:?}", date.feedback);
+    let walk = estimate("sdfghjk", &[]);
+    assert!(walk.feedback.iter().any(|hint| hint.contains("Keyboard")), "{:?}", walk.feedback);
+}
+
+#[test]
+fn test_the_entry_name_and_username_count_against_a_password() {
+    let alone = estimate("octocat2019!", &[]);
+    let personal = estimate("octocat2019!", &["github", "octocat"]);
+    assert!(personal.entropy < alone.entropy);
+    assert!(personal.feedback.iter().any(|hint| hint.contains("username")), "{:?}", personal.feedback);
+}
+
+#[test]
+fn test_long_generated_passwords_and_passphrases_are_strong() {
+    // The old 8-21 rule rejected both of these
+    let password = generate_password(&PasswordOptions { length: 32, ..Default::default() }).unwrap();
+    assert_eq!(estimate(password.expose(), &[]).score, 4);
+
+    let passphrase = generate_passphrase(&PassphraseOptions::default()).unwrap();
+    let strength = estimate(passphrase.expose(), &[]);
+    assert!(strength.score >= 3, "{} scored {}", passphrase.expose(), strength.score);
+    assert!(strength.feedback.is_empty());
+
+    // Short random passwords are still fine
+    assert!(estimate("x7Kp#2qL", &[]).score >= 2);
+}
+
+#[test]
+fn test_minimum_score_rejects_or_warns() {
+    let weak = estimate("password1", &[]);
+    let error = check_strength(&weak, 2, false).unwrap_err();
+    assert!(error.contains("very weak"), "{}", error);
+    assert!(error.contains("common"), "{}", error);
+
+    let warning = check_strength(&weak, 2, true).unwrap();
+    assert!(warning.is_some());
+    assert_eq!(check_strength(&weak, 0, false), Ok(None));
+
+    let strong = estimate("correct-horse-battery-staple-42", &[]);
+    assert_eq!(check_strength(&strong, 3, false), Ok(None));
+}
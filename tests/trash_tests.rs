@@ -0,0 +1,73 @@
+use std::fs;
+
+use rustpass::data::data::{create_password_file, load_passwords, save_passwords, update_passwords};
+use rustpass::data::folders::list_folders;
+use rustpass::data::trash::{
+    empty_trash, list_trash, move_to_trash, purge_expired, purge_from_trash, restore_from_trash, split_trash,
+};
+
+mod common;
+use common::{days_ago, entry, temp_vault_path};
+
+#[test]
+fn test_deleted_entries_go_to_the_trash_and_back() {
+    let mut passwords = vec![entry("a"), entry("b"), entry("c")];
+    passwords[1].folder = "work".into();
+    let id = passwords[1].id.clone();
+
+    assert_eq!(move_to_trash(&mut passwords, |e| e.name != "c"), 2);
+    // Already trashed entries are left alone
+    assert_eq!(move_to_trash(&mut passwords, |e| e.name == "a"), 0);
+    assert!(list_folders(&passwords).is_empty());
+
+    let (live, trash) = split_trash(passwords.clone());
+    assert_eq!(live.len(), 1);
+    assert_eq!(trash.len(), 2);
+
+    restore_from_trash(&mut passwords, &id).unwrap();
+    assert!(restore_from_trash(&mut passwords, &id).is_err());
+    assert_eq!(list_folders(&passwords), vec!["work"]);
+    assert_eq!(list_trash(&passwords).len(), 1);
+
+    let trashed = list_trash(&passwords)[0].id.clone();
+    purge_from_trash(&mut passwords, &trashed).unwrap();
+    assert_eq!(passwords.len(), 2);
+    // Live entries can't be purged
+    let live_id = passwords[0].id.clone();
+    assert!(purge_from_trash(&mut passwords, &live_id).is_err());
+    assert_eq!(empty_trash(&mut passwords), 0);
+}
+
+#[test]
+fn test_expired_trash_is_purged() {
+    let mut passwords = vec![entry("old"), entry("recent"), entry("live")];
+    passwords[0].times.deleted = days_ago(45);
+    passwords[1].times.deleted = days_ago(5);
+
+    assert_eq!(purge_expired(&mut passwords, 0), 0);
+    assert_eq!(purge_expired(&mut passwords, 30), 1);
+    let names: Vec<&str> = passwords.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["recent", "live"]);
+}
+
+#[test]
+fn test_saving_purges_expired_trash() {
+    let path = temp_vault_path();
+
+    let (_, key, mut header) = create_password_file(&path, "correct horse").unwrap();
+    let mut passwords = vec![entry("old"), entry("live")];
+    passwords[0].times.deleted = days_ago(400);
+    save_passwords(&path, &passwords, &key, &mut header).unwrap();
+
+    update_passwords(&path, &key, &mut header, |passwords| {
+        move_to_trash(passwords, |e| e.name == "live");
+        Ok(())
+    })
+    .unwrap();
+
+    let (passwords, _, _) = load_passwords(&path, "correct horse", None).unwrap();
+    assert_eq!(passwords.len(), 1);
+    assert!(passwords[0].is_trashed());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
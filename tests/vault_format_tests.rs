@@ -0,0 +1,340 @@
+use std::fs;
+
+use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+use rustpass::auth::handler::{derive_key, derive_key_with_params};
+use rustpass::data::data::{
+    change_master_password, create_password_file, create_vault, load_passwords, save_passwords,
+    update_key_slots,
+};
+use rustpass::auth::keyslots::{create_key_slot, unlock_header, UnlockSecret};
+use rustpass::config::settings::SETTINGS;
+use rustpass::data::revision::{last_seen_revision, set_accept_rollback};
+use rustpass::data::verify::verify_vault;
+use rustpass::encryption::encrypt::encrypt_data;
+use rustpass::models::structs::{
+    CipherKind, KdfParams, KdfVersion, KeySlotKind, PasswordEntry, VAULT_FORMAT_VERSION,
+};
+
+mod common;
+use common::temp_vault_path;
+
+#[test]
+fn test_new_vault_records_header() {
+    let path = temp_vault_path();
+    create_password_file(&path, "correct horse").unwrap();
+
+    let raw: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(raw["version"], VAULT_FORMAT_VERSION);
+    // New vaults use the configured cipher
+    assert_eq!(raw["cipher"], serde_json::to_value(SETTINGS.vault_cipher).unwrap());
+    assert_eq!(raw["key_slots"][0]["kind"], "password");
+    assert_eq!(raw["key_slots"][0]["kdf"]["algorithm"], "argon2id");
+
+    let (_, _, header) = load_passwords(&path, "correct horse", None).unwrap();
+    assert_eq!(header.key_slots.len(), 1);
+    assert_eq!(header.key_slots[0].kdf, KdfParams::default());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_legacy_vault_is_migrated_to_key_slots() {
+    let path = temp_vault_path();
+
+    // Original layout: salt/nonce/encrypted_data, payload keyed by the password directly
+    let salt = [7u8; 16];
+    let key = derive_key_with_params("correct horse", None, &salt, &KdfParams::legacy()).unwrap();
+    let nonce = [3u8; 12];
+    let ciphertext = Aes256Gcm::new(key.expose().into())
+        .encrypt(&nonce.into(), b"[]".as_ref())
+        .unwrap();
+    let legacy = serde_json::json!({
+        "salt": STANDARD.encode(salt),
+        "nonce": STANDARD.encode(nonce),
+        "encrypted_data": STANDARD.encode(ciphertext),
+    });
+    fs::write(&path, legacy.to_string()).unwrap();
+
+    let (passwords, data_key, header) = load_passwords(&path, "correct horse", None).unwrap();
+    assert!(passwords.is_empty());
+    assert_eq!(data_key, key);
+    assert_eq!(header.key_slots.len(), 1);
+    assert_eq!(header.key_slots[0].kind, KeySlotKind::Password);
+    assert_eq!(header.key_slots[0].kdf.version, KdfVersion::V2);
+
+    let raw: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(raw["version"], VAULT_FORMAT_VERSION);
+    assert!(raw.get("salt").is_none());
+    assert!(load_passwords(&path, "correct horse", None).is_ok());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_unknown_version_is_rejected() {
+    let path = temp_vault_path();
+    create_password_file(&path, "correct horse").unwrap();
+
+    let mut raw: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    raw["version"] = serde_json::json!(99);
+    fs::write(&path, raw.to_string()).unwrap();
+
+    let err = load_passwords(&path, "correct horse", None).unwrap_err();
+    assert!(err.contains("Unsupported vault format version 99"), "{}", err);
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_change_master_password_rekeys_vault() {
+    let path = temp_vault_path();
+    let (_, old_key, old_header) = create_password_file(&path, "correct horse").unwrap();
+
+    assert!(change_master_password(&path, "wrong", "battery staple", None, &[]).is_err());
+
+    let (_, new_key, new_header) =
+        change_master_password(&path, "correct horse", "battery staple", None, &[]).unwrap();
+    assert_ne!(new_key, old_key);
+    assert_ne!(new_header.key_slots[0].salt, old_header.key_slots[0].salt);
+
+    assert!(load_passwords(&path, "correct horse", None).is_err());
+    assert!(load_passwords(&path, "battery staple", None).is_ok());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_change_master_password_keeps_other_password_slots() {
+    let path = temp_vault_path();
+    let (_, key, mut header) = create_password_file(&path, "correct horse").unwrap();
+    let second = create_key_slot(&key, &UnlockSecret::Password("second pass", None), "password").unwrap();
+    let second_id = second.id.clone();
+    update_key_slots(&path, &key, &mut header, |slots| {
+        slots.push(second);
+        Ok(())
+    })
+    .unwrap();
+
+    // The master password slot can't move to the new key without its password
+    let err = change_master_password(&path, "second pass", "second renewed", None, &[]).unwrap_err();
+    assert!(err.contains(&header.key_slots[0].id), "{}", err);
+    assert!(load_passwords(&path, "second pass", None).is_ok());
+
+    // Changing the second password leaves the master password alone, and the other way round
+    let master = [UnlockSecret::Password("correct horse", None)];
+    let (_, new_key, header) =
+        change_master_password(&path, "second pass", "second renewed", None, &master).unwrap();
+    assert_ne!(new_key, key);
+    assert_eq!(header.key_slots.len(), 2);
+    assert_ne!(header.key_slots[1].id, second_id);
+    assert!(load_passwords(&path, "second pass", None).is_err());
+    assert!(load_passwords(&path, "second renewed", None).is_ok());
+    assert!(load_passwords(&path, "correct horse", None).is_ok());
+
+    let second = [UnlockSecret::Password("second renewed", None)];
+    change_master_password(&path, "correct horse", "battery staple", None, &second).unwrap();
+    assert!(load_passwords(&path, "battery staple", None).is_ok());
+    assert!(load_passwords(&path, "second renewed", None).is_ok());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_kdf_v2_keeps_non_ascii_passwords_apart() {
+    let salt = [1u8; 16];
+    let legacy = KdfParams::legacy();
+
+    // `fibbil_hash` folds 'ä' (U+00E4) onto 'd'
+    assert_eq!(
+        derive_key_with_params("pä", None, &salt, &legacy).unwrap(),
+        derive_key_with_params("pd", None, &salt, &legacy).unwrap()
+    );
+    assert_ne!(derive_key("pä", None, &salt).unwrap(), derive_key("pd", None, &salt).unwrap());
+
+    // Composed and decomposed forms of the same password derive the same key
+    assert_eq!(
+        derive_key("p\u{e4}", None, &salt).unwrap(),
+        derive_key("pa\u{308}", None, &salt).unwrap()
+    );
+}
+
+#[test]
+fn test_kdf_v1_slot_is_upgraded_on_unlock() {
+    let path = temp_vault_path();
+    let (_, data_key, _) = create_password_file(&path, "correct horse").unwrap();
+
+    // Re-wrap the password slot the way older builds did, in a format 3 file whose
+    // header is not authenticated
+    let mut raw: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    let (nonce, entries) = encrypt_data(b"[]", data_key.expose()).unwrap();
+    raw["version"] = 3.into();
+    raw["nonce"] = STANDARD.encode(nonce).into();
+    raw["encrypted_data"] = STANDARD.encode(entries).into();
+    let salt = STANDARD.decode(raw["key_slots"][0]["salt"].as_str().unwrap()).unwrap();
+    let kek = derive_key_with_params("correct horse", None, &salt, &KdfParams::legacy()).unwrap();
+    let (nonce, wrapped_key) = encrypt_data(data_key.expose(), &kek).unwrap();
+    raw["key_slots"][0]["kdf"].as_object_mut().unwrap().remove("version");
+    raw["key_slots"][0]["nonce"] = STANDARD.encode(nonce).into();
+    raw["key_slots"][0]["wrapped_key"] = STANDARD.encode(wrapped_key).into();
+    let slot_id = raw["key_slots"][0]["id"].clone();
+    // A different file, as this one was already seen at a newer revision
+    let path = path.with_file_name("v3.json");
+    fs::write(&path, raw.to_string()).unwrap();
+
+    let (_, key, header) = load_passwords(&path, "correct horse", None).unwrap();
+    assert_eq!(key, data_key);
+    assert_eq!(header.key_slots[0].kdf.version, KdfVersion::V2);
+
+    let raw: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(raw["key_slots"][0]["kdf"]["version"], "v2");
+    assert_eq!(raw["key_slots"][0]["id"], slot_id);
+    assert!(load_passwords(&path, "correct horse", None).is_ok());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_xchacha20_vault_roundtrip() {
+    let path = temp_vault_path();
+    let (_, key, mut header) =
+        create_vault(&path, "correct horse", None, CipherKind::XChaCha20Poly1305, Vec::new()).unwrap();
+
+    let entry = PasswordEntry::new("mail".into(), "me@example.com".into(), "hunter2".into());
+    save_passwords(&path, &[entry], &key, &mut header).unwrap();
+
+    let raw: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(raw["cipher"], "xchacha20-poly1305");
+    assert_eq!(STANDARD.decode(raw["nonce"].as_str().unwrap()).unwrap().len(), 24);
+
+    let (passwords, _, _) = load_passwords(&path, "correct horse", None).unwrap();
+    assert_eq!(passwords[0].password.expose(), "hunter2");
+
+    // The cipher stays with the vault when the master password changes
+    let (_, _, header) =
+        change_master_password(&path, "correct horse", "battery staple", None, &[]).unwrap();
+    assert_eq!(header.cipher, CipherKind::XChaCha20Poly1305);
+    assert!(load_passwords(&path, "battery staple", None).is_ok());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_modified_header_is_rejected() {
+    let path = temp_vault_path();
+    create_vault(&path, "correct horse", None, CipherKind::XChaCha20Poly1305, Vec::new()).unwrap();
+    let original = fs::read_to_string(&path).unwrap();
+
+    // Relabelling a slot still lets it unwrap the key, but the entries no longer decrypt
+    let mut raw: serde_json::Value = serde_json::from_str(&original).unwrap();
+    raw["key_slots"][0]["label"] = "tampered".into();
+    fs::write(&path, raw.to_string()).unwrap();
+    assert!(load_passwords(&path, "correct horse", None).is_err());
+
+    let report = verify_vault(&path, |header| {
+        unlock_header(header, &UnlockSecret::Password("correct horse", None))
+    });
+    assert!(!report.is_ok());
+    assert!(report.errors[0].contains("header or the entries were modified"), "{:?}", report);
+
+    // The generation is not bound, so a restored backup can move it
+    let mut raw: serde_json::Value = serde_json::from_str(&original).unwrap();
+    raw["generation"] = 42.into();
+    fs::write(&path, raw.to_string()).unwrap();
+    let report = verify_vault(&path, |header| {
+        unlock_header(header, &UnlockSecret::Password("correct horse", None))
+    });
+    assert!(report.is_ok(), "{:?}", report);
+    assert_eq!(report.entries, Some(0));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_verify_reports_broken_layout() {
+    let path = temp_vault_path();
+    create_password_file(&path, "correct horse").unwrap();
+
+    let mut raw: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    raw["nonce"] = STANDARD.encode([0u8; 5]).into();
+    raw["key_slots"][0]["wrapped_key"] = STANDARD.encode([0u8; 10]).into();
+    fs::write(&path, raw.to_string()).unwrap();
+
+    // Layout problems are reported without asking for the password
+    let report = verify_vault(&path, |_| panic!("should not unlock a broken vault"));
+    assert_eq!(report.errors.len(), 2, "{:?}", report);
+    assert!(report.errors.iter().any(|e| e.contains("wrapped key")));
+    assert!(report.errors.iter().any(|e| e.contains("Entry nonce is 5 bytes")));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_rolled_back_vault_is_refused() {
+    let path = temp_vault_path();
+    let (_, key, mut header) = create_password_file(&path, "correct horse").unwrap();
+    let old_copy = fs::read_to_string(&path).unwrap();
+    save_passwords(&path, &[], &key, &mut header).unwrap();
+    assert_eq!(header.revision, 2);
+    assert_eq!(last_seen_revision(&path), Some(2));
+
+    // An older copy still decrypts, but is refused until explicitly accepted
+    fs::write(&path, &old_copy).unwrap();
+    let err = load_passwords(&path, "correct horse", None).unwrap_err();
+    assert!(err.contains("--accept-rollback"), "{}", err);
+
+    // Claiming a newer revision breaks the authenticated header
+    let mut raw: serde_json::Value = serde_json::from_str(&old_copy).unwrap();
+    raw["revision"] = 3.into();
+    fs::write(&path, raw.to_string()).unwrap();
+    assert!(load_passwords(&path, "correct horse", None).is_err());
+
+    fs::write(&path, &old_copy).unwrap();
+    set_accept_rollback(true);
+    let loaded = load_passwords(&path, "correct horse", None);
+    set_accept_rollback(false);
+    assert!(loaded.is_ok());
+    assert_eq!(last_seen_revision(&path), Some(1));
+    assert!(load_passwords(&path, "correct horse", None).is_ok());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_login_in_id_is_moved_to_username() {
+    let path = temp_vault_path();
+    let (_, key, mut header) = create_password_file(&path, "correct horse").unwrap();
+
+    // Entries from before the username field, one of them stored twice
+    let legacy = |name: &str, login: &str| PasswordEntry {
+        id: login.into(),
+        ..PasswordEntry::new(name.into(), String::new(), "hunter2".into())
+    };
+    let entries = [legacy("github", "octocat"), legacy("github", "octocat"), legacy("mail", "me")];
+    save_passwords(&path, &entries, &key, &mut header).unwrap();
+
+    let (first, _, _) = load_passwords(&path, "correct horse", None).unwrap();
+    assert_eq!(first[0].username, "octocat");
+    assert_eq!(first[2].username, "me");
+    assert!(uuid::Uuid::parse_str(&first[0].id).is_ok());
+    assert_ne!(first[0].id, first[1].id);
+
+    // Every reader derives the same ids until the migrated entries are saved
+    let (second, _, _) = load_passwords(&path, "correct horse", None).unwrap();
+    let ids = |entries: &[PasswordEntry]| entries.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+    assert_eq!(ids(&first), ids(&second));
+
+    // Entries created with a username are left alone
+    let entry = PasswordEntry::new("bank".into(), "".into(), "hunter2".into());
+    let id = entry.id.clone();
+    save_passwords(&path, &[entry], &key, &mut header).unwrap();
+    let (passwords, _, _) = load_passwords(&path, "correct horse", None).unwrap();
+    assert_eq!(passwords[0].id, id);
+    assert!(passwords[0].username.is_empty());
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
@@ -0,0 +1,47 @@
+use std::fs;
+
+use rustpass::data::data::{create_password_file, load_passwords, save_passwords, update_passwords};
+
+mod common;
+use common::{entry, temp_vault_path};
+
+#[test]
+fn test_stale_save_is_refused() {
+    let path = temp_vault_path();
+    create_password_file(&path, "correct horse").unwrap();
+
+    // Two processes load the same generation
+    let (_, key, mut first) = load_passwords(&path, "correct horse", None).unwrap();
+    let (_, _, mut second) = load_passwords(&path, "correct horse", None).unwrap();
+
+    save_passwords(&path, &[entry("github")], &key, &mut first).unwrap();
+    let err = save_passwords(&path, &[entry("gitlab")], &key, &mut second).unwrap_err();
+    assert!(err.contains("modified by another process"), "{}", err);
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_update_merges_concurrent_changes() {
+    let path = temp_vault_path();
+    create_password_file(&path, "correct horse").unwrap();
+
+    let (_, key, mut first) = load_passwords(&path, "correct horse", None).unwrap();
+    let (_, _, mut second) = load_passwords(&path, "correct horse", None).unwrap();
+
+    update_passwords(&path, &key, &mut first, |p| {
+        p.push(entry("github"));
+        Ok(())
+    })
+    .unwrap();
+    let (passwords, ()) = update_passwords(&path, &key, &mut second, |p| {
+        p.push(entry("gitlab"));
+        Ok(())
+    })
+    .unwrap();
+
+    assert_eq!(passwords.len(), 2);
+    assert_eq!(load_passwords(&path, "correct horse", None).unwrap().0.len(), 2);
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
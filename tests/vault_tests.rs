@@ -0,0 +1,46 @@
+use rustpass::cli::handler::extract_global_options;
+use rustpass::data::vault::validate_vault_name;
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_extract_vault_option() {
+    let mut argv = args(&["rsp", "--vault", "work", "list"]);
+    let options = extract_global_options(&mut argv).unwrap();
+    assert_eq!(options.vault.as_deref(), Some("work"));
+    assert_eq!(argv, args(&["rsp", "list"]));
+
+    let mut argv = args(&["rsp", "remove", "github", "--vault=personal"]);
+    let options = extract_global_options(&mut argv).unwrap();
+    assert_eq!(options.vault.as_deref(), Some("personal"));
+    assert_eq!(argv, args(&["rsp", "remove", "github"]));
+
+    let mut argv = args(&["rsp", "list", "--vault"]);
+    assert!(extract_global_options(&mut argv).is_err());
+}
+
+#[test]
+fn test_extract_keyfile_option() {
+    let mut argv = args(&["rsp", "--keyfile", "/tmp/rsp.key", "unlock", "--vault=work"]);
+    let options = extract_global_options(&mut argv).unwrap();
+    assert_eq!(options.keyfile.as_deref(), Some("/tmp/rsp.key"));
+    assert_eq!(options.vault.as_deref(), Some("work"));
+    assert!(!options.accept_rollback);
+    assert_eq!(argv, args(&["rsp", "unlock"]));
+
+    let mut argv = args(&["rsp", "list", "--accept-rollback"]);
+    assert!(extract_global_options(&mut argv).unwrap().accept_rollback);
+    assert_eq!(argv, args(&["rsp", "list"]));
+}
+
+#[test]
+fn test_vault_name_validation() {
+    for name in ["default", "work", "team-shared", "v2_backup"] {
+        assert!(validate_vault_name(name).is_ok(), "{} should be valid", name);
+    }
+    for name in ["", ".hidden", "../etc", "a/b", "with space"] {
+        assert!(validate_vault_name(name).is_err(), "{} should be invalid", name);
+    }
+}